@@ -0,0 +1,216 @@
+use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+use windows::{
+    Win32::{
+        Foundation::{
+            ERROR_ACCESS_DENIED, ERROR_INVALID_WINDOW_HANDLE, HWND, LPARAM, RECT, WPARAM,
+        },
+        UI::WindowsAndMessaging::{
+            EnumWindows, GW_OWNER, GetClassNameW, GetWindow, GetWindowRect, GetWindowTextLengthW,
+            GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow, IsWindowVisible,
+            PostMessageW, SHOW_WINDOW_CMD, SW_MINIMIZE, SW_RESTORE, SetForegroundWindow,
+            ShowWindowAsync, WM_CLOSE,
+        },
+    },
+    core::{BOOL, Error, HRESULT},
+};
+
+/// Longest window class name Windows allows, plus its terminator.
+const MAX_CLASS_NAME: usize = 257;
+
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub handle: HWND,
+    pub pid: u32,
+}
+
+impl Window {
+    pub fn title(&self) -> String {
+        let len = unsafe { GetWindowTextLengthW(self.handle) };
+        if len <= 0 {
+            return String::new();
+        }
+        let mut buffer = vec![0u16; len as usize + 1];
+        let copied = unsafe { GetWindowTextW(self.handle, &mut buffer) };
+        OsString::from_wide(&buffer[..copied.max(0) as usize])
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn class_name(&self) -> String {
+        let mut buffer = [0u16; MAX_CLASS_NAME];
+        let copied = unsafe { GetClassNameW(self.handle, &mut buffer) };
+        OsString::from_wide(&buffer[..copied.max(0) as usize])
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(self.handle) }.as_bool()
+    }
+
+    /// Whether another window owns this one, as it does its dialogs and
+    /// tool windows.
+    pub fn is_owned(&self) -> bool {
+        unsafe { GetWindow(self.handle, GW_OWNER) }.is_ok_and(|owner| !owner.is_invalid())
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        unsafe { IsIconic(self.handle) }.as_bool()
+    }
+
+    /// The window's bounds in screen coordinates.
+    pub fn rect(&self) -> Result<RECT, Error> {
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(self.handle, &mut rect) }?;
+        Ok(rect)
+    }
+}
+
+unsafe extern "system" fn collect_window(handle: HWND, windows: LPARAM) -> BOOL {
+    let windows = unsafe { &mut *(windows.0 as *mut Vec<Window>) };
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(handle, Some(&mut pid)) };
+    windows.push(Window { handle, pid });
+    true.into()
+}
+
+pub struct WindowManager;
+
+impl WindowManager {
+    pub fn list(&self) -> Result<Vec<Window>, Error> {
+        let mut windows = Vec::new();
+        unsafe {
+            EnumWindows(
+                Some(collect_window),
+                LPARAM(&mut windows as *mut Vec<Window> as isize),
+            )
+        }?;
+        Ok(windows)
+    }
+
+    /// Looks up a top-level window by the handle [`Self::list`] reported,
+    /// failing with `ERROR_INVALID_WINDOW_HANDLE` once it is gone.
+    pub fn get(&self, handle: u64) -> Result<Window, Error> {
+        let handle = HWND(handle as usize as *mut _);
+        if !unsafe { IsWindow(Some(handle)) }.as_bool() {
+            return Err(Error::from_hresult(HRESULT::from_win32(
+                ERROR_INVALID_WINDOW_HANDLE.0,
+            )));
+        }
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(handle, Some(&mut pid)) };
+        Ok(Window { handle, pid })
+    }
+
+    /// Asks the window to close, as if the user clicked its close button.
+    pub fn close(&self, window: &Window) -> Result<(), Error> {
+        unsafe { PostMessageW(Some(window.handle), WM_CLOSE, WPARAM(0), LPARAM(0)) }
+    }
+
+    pub fn minimize(&self, window: &Window) -> Result<(), Error> {
+        self.show(window, SW_MINIMIZE)
+    }
+
+    /// Restores a minimized or maximized window to its normal size and
+    /// position.
+    pub fn restore(&self, window: &Window) -> Result<(), Error> {
+        self.show(window, SW_RESTORE)
+    }
+
+    /// Brings the window to the front and gives it keyboard focus, failing
+    /// with `ERROR_ACCESS_DENIED` when the foreground lock refuses it.
+    pub fn foreground(&self, window: &Window) -> Result<(), Error> {
+        if !unsafe { SetForegroundWindow(window.handle) }.as_bool() {
+            return Err(Error::from_hresult(HRESULT::from_win32(
+                ERROR_ACCESS_DENIED.0,
+            )));
+        }
+        Ok(())
+    }
+
+    /// Changes the window's show state without waiting for its thread, so a
+    /// hung program cannot block the bridge.
+    fn show(&self, window: &Window, command: SHOW_WINDOW_CMD) -> Result<(), Error> {
+        if !unsafe { ShowWindowAsync(window.handle, command) }.as_bool() {
+            return Err(Error::from_thread());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// How long a child is given to open its window, or to exit once it is
+    /// closed.
+    const CHILD_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The first visible top-level window of process `pid`, polled for
+    /// until a freshly started program has had time to create it.
+    fn window_of(pid: u32) -> Option<Window> {
+        let deadline = Instant::now() + CHILD_TIMEOUT;
+        while Instant::now() < deadline {
+            let window = WindowManager
+                .list()
+                .unwrap()
+                .into_iter()
+                .find(|window| window.pid == pid && window.is_visible());
+            if window.is_some() {
+                return window;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        None
+    }
+
+    #[test]
+    fn lists_the_window_of_a_child() {
+        let mut child = std::process::Command::new("notepad").spawn().unwrap();
+        let window = window_of(child.id());
+        let looked_up = window
+            .as_ref()
+            .map(|window| WindowManager.get(window.handle.0 as usize as u64));
+        let details = window
+            .as_ref()
+            .map(|window| (window.class_name(), window.rect().is_ok()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(details, Some(("Notepad".to_string(), true)));
+        assert_eq!(looked_up.unwrap().unwrap().pid, child.id());
+    }
+
+    #[test]
+    fn closes_the_window_of_a_child() {
+        let mut child = std::process::Command::new("notepad").spawn().unwrap();
+        let Some(window) = window_of(child.id()) else {
+            child.kill().unwrap();
+            panic!("notepad opened no window");
+        };
+
+        WindowManager.close(&window).unwrap();
+        let deadline = Instant::now() + CHILD_TIMEOUT;
+        let exited = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break Some(status);
+            }
+            if Instant::now() >= deadline {
+                child.kill().unwrap();
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+
+        assert!(exited.is_some_and(|status| status.success()));
+        assert_eq!(
+            WindowManager
+                .get(window.handle.0 as usize as u64)
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_INVALID_WINDOW_HANDLE.0))
+        );
+    }
+}
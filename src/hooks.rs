@@ -0,0 +1,156 @@
+use std::{sync::Arc, time::Duration};
+
+use next_proto::winebridge::{self, launch_action::Action};
+use tonic::Status;
+
+use crate::dll_overrides::manager::DllOverrideManager;
+use crate::processes::launch::Launch;
+use crate::processes::manager::ProcessManager;
+use crate::registry::operations;
+use crate::{executable_path, required, status};
+
+/// Checks the actions a launch runs around its lifetime before any of them
+/// runs, so a malformed one cannot fail halfway through.
+pub fn validate(actions: &[winebridge::LaunchAction]) -> Result<(), Status> {
+    for action in actions {
+        match &action.action {
+            None => return Err(Status::invalid_argument("launch action is required")),
+            Some(Action::RunCommand(command)) => {
+                executable_path(&command.executable, "action executable")?;
+                if command
+                    .arguments
+                    .iter()
+                    .chain(&command.working_directory)
+                    .any(|argument| argument.contains('\0'))
+                {
+                    return Err(Status::invalid_argument(
+                        "action arguments and working directory must contain no NUL bytes",
+                    ));
+                }
+                if command.timeout_ms == Some(0) {
+                    return Err(Status::invalid_argument("action timeout must be non-zero"));
+                }
+            }
+            Some(Action::SetRegistryValue(set)) => {
+                operations::validate_address(set.hive, &set.subkey, &set.name)?;
+                let value = set
+                    .value
+                    .as_ref()
+                    .and_then(|value| value.value.as_ref())
+                    .ok_or_else(|| Status::invalid_argument("registry value is required"))?;
+                operations::validate_value(value)?;
+            }
+            Some(Action::DeleteRegistryValue(delete)) => {
+                operations::validate_address(delete.hive, &delete.subkey, &delete.name)?;
+            }
+            Some(Action::SetDllOverride(set)) => {
+                required(&set.dll, "DLL name")?;
+                let mode = winebridge::DllOverrideMode::try_from(set.mode)
+                    .map_err(|_| Status::invalid_argument("invalid DLL override mode"))?;
+                if mode == winebridge::DllOverrideMode::Unspecified {
+                    return Err(Status::invalid_argument("DLL override mode is required"));
+                }
+            }
+            Some(Action::DeleteDllOverride(delete)) => required(&delete.dll, "DLL name")?,
+        }
+    }
+    Ok(())
+}
+
+/// Runs the pre-launch `actions` of a launch in order, stopping at the
+/// first one that fails.
+pub fn run(processes: &ProcessManager, actions: &[winebridge::LaunchAction]) -> Result<(), Status> {
+    actions
+        .iter()
+        .try_for_each(|action| run_action(processes, action))
+}
+
+/// Runs `actions` on a dedicated thread once every process of `launch` has
+/// exited, or right away when the launch never started. Nothing waits for
+/// them, so a failing action is logged and the next one still runs.
+pub fn run_after_exit(
+    processes: Arc<ProcessManager>,
+    launch: Option<Arc<Launch>>,
+    id: String,
+    actions: Vec<winebridge::LaunchAction>,
+) {
+    if actions.is_empty() {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Some(launch) = launch
+            && let Err(error) = launch.wait(None)
+        {
+            tracing::warn!("Failed to wait for launch {id} before its post-exit actions: {error}");
+        }
+        for action in &actions {
+            if let Err(error) = run_action(&processes, action) {
+                tracing::warn!(
+                    "Post-exit action of launch {id} failed: {}",
+                    error.message()
+                );
+            }
+        }
+    });
+}
+
+/// Runs one action, reporting its failure as the status a client would get.
+fn run_action(processes: &ProcessManager, action: &winebridge::LaunchAction) -> Result<(), Status> {
+    match &action.action {
+        None => Err(Status::invalid_argument("launch action is required")),
+        Some(Action::RunCommand(command)) => run_command(processes, command),
+        Some(Action::SetRegistryValue(set)) => {
+            let value = set
+                .value
+                .clone()
+                .and_then(|value| value.value)
+                .ok_or_else(|| Status::invalid_argument("registry value is required"))?;
+            operations::set_value(set.hive, &set.subkey, &set.name, value)
+        }
+        Some(Action::DeleteRegistryValue(delete)) => {
+            operations::delete_value(delete.hive, &delete.subkey, &delete.name)
+        }
+        Some(Action::SetDllOverride(set)) => DllOverrideManager
+            .set(&set.dll, set.mode())
+            .map_err(status::windows),
+        Some(Action::DeleteDllOverride(delete)) => DllOverrideManager
+            .delete(&delete.dll)
+            .map_err(status::windows),
+    }
+}
+
+/// Runs a command and waits for it, failing when it exits unsuccessfully or
+/// outlives its timeout, in which case it is terminated. It is not a launch
+/// of its own, so clients never see it, and it dies with the bridge.
+fn run_command(
+    processes: &ProcessManager,
+    command: &winebridge::RunCommandAction,
+) -> Result<(), Status> {
+    let request = winebridge::LaunchProcessRequest {
+        executable: command.executable.clone(),
+        arguments: command.arguments.clone(),
+        working_directory: command.working_directory.clone(),
+        ..Default::default()
+    };
+    let timeout = command
+        .timeout_ms
+        .map(|ms| Duration::from_millis(ms.into()));
+
+    let output = processes
+        .run_unregistered(request, timeout, 0)
+        .map_err(status::windows)?;
+
+    if output.timed_out {
+        return Err(Status::deadline_exceeded(format!(
+            "action command {} timed out",
+            command.executable
+        )));
+    }
+    if output.exit.exit_code != 0 {
+        return Err(Status::aborted(format!(
+            "action command {} exited with {}",
+            command.executable, output.exit.exit_code
+        )));
+    }
+    Ok(())
+}
@@ -1,26 +1,63 @@
+mod desktop;
 mod dll_overrides;
+mod hooks;
 mod processes;
 mod registry;
 mod services;
 mod status;
+mod stream;
 
+use desktop::manager::WindowManager;
 use dll_overrides::manager::DllOverrideManager;
-use next_proto::winebridge::{self, wine_bridge_server::WineBridge};
-use processes::manager::ProcessManager;
+use next_proto::winebridge::{self, console_input::Input, wine_bridge_server::WineBridge};
+use processes::launch::Launch;
+use processes::manager::{ProcessManager, Reservation};
+use processes::process::ProcessHandle;
+use processes::thread::ThreadHandle;
 use registry::operations;
 use services::manager::ServiceManager;
 use std::ffi::OsString;
 use std::os::windows::ffi::{OsStrExt, OsStringExt};
 use std::path::Path;
-use tokio::sync::{Mutex, oneshot};
-use tonic::{Request, Response, Result, Status};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use stream::ReceiverStream;
+use tokio::sync::{Mutex, broadcast, mpsc, oneshot};
+use tonic::{Request, Response, Result, Status, Streaming};
 use windows::Win32::Foundation::CloseHandle;
 use windows::Win32::Storage::FileSystem::{
     GetDiskFreeSpaceExW, GetLogicalDrives, GetVolumeInformationW,
 };
-use windows::Win32::System::Threading::{CREATE_NEW_CONSOLE, CreateProcessW, STARTUPINFOW};
+use windows::Win32::System::Threading::{
+    CREATE_NEW_CONSOLE, CreateProcessW, STARTUPINFOW, THREAD_QUERY_LIMITED_INFORMATION,
+};
 use windows::core::PCWSTR;
 
+/// Output events buffered per streaming launch before the pipe readers block.
+const OUTPUT_EVENT_BUFFER: usize = 64;
+
+/// Shortest sampling interval `MonitorProcesses` accepts.
+const MIN_MONITOR_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bytes of each output stream `RunCommand` keeps unless asked otherwise.
+const DEFAULT_RUN_OUTPUT_LIMIT: u64 = 1024 * 1024;
+
+/// Most bytes of each output stream `RunCommand` keeps, so both fit in one
+/// response under the default 4 MiB gRPC message size limit.
+const MAX_RUN_OUTPUT_LIMIT: u64 = 3 * 1024 * 1024 / 2;
+
+/// Frames of each thread `GetProcessStacks` walks unless asked otherwise.
+const DEFAULT_STACK_FRAMES: u32 = 128;
+
+/// Most frames of each thread `GetProcessStacks` walks.
+const MAX_STACK_FRAMES: u32 = 1024;
+
+/// Console size used when a session does not ask for one.
+const DEFAULT_CONSOLE_SIZE: winebridge::ConsoleSize = winebridge::ConsoleSize {
+    columns: 80,
+    rows: 25,
+};
+
 fn to_wide(s: &str) -> Vec<u16> {
     OsString::from(s).encode_wide().chain(Some(0)).collect()
 }
@@ -64,6 +101,178 @@ fn required(value: &str, field: &str) -> Result<(), Status> {
     }
 }
 
+/// Checks an executable path, which is quoted as a whole on the command line
+/// and so cannot contain quotes itself.
+fn executable_path(value: &str, field: &str) -> Result<(), Status> {
+    required(value, field)?;
+    if value.contains('"') {
+        return Err(Status::invalid_argument(format!(
+            "{field} must contain no quotes"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_launch(input: &winebridge::LaunchProcessRequest) -> Result<(), Status> {
+    if input.id.is_empty()
+        || input.id.contains('\0')
+        || input.executable.is_empty()
+        || input.executable.contains('\0')
+        || input
+            .arguments
+            .iter()
+            .any(|argument| argument.contains('\0'))
+        || input
+            .working_directory
+            .as_deref()
+            .is_some_and(|directory| directory.contains('\0'))
+        || input
+            .crash_dump_directory
+            .as_deref()
+            .is_some_and(|directory| directory.is_empty() || directory.contains('\0'))
+    {
+        return Err(Status::invalid_argument(
+            "process paths and arguments must be non-empty where required and contain no NUL bytes",
+        ));
+    }
+    executable_path(&input.executable, "executable path")?;
+    if let Some(command_line) = &input.command_line {
+        if command_line.contains('\0') {
+            return Err(Status::invalid_argument(
+                "command line must contain no NUL bytes",
+            ));
+        }
+        if !input.arguments.is_empty() {
+            return Err(Status::invalid_argument(
+                "arguments and a raw command line are mutually exclusive",
+            ));
+        }
+    }
+    if input
+        .environment
+        .keys()
+        .chain(&input.unset_environment)
+        .any(|name| name.is_empty() || name.contains(['=', '\0']))
+        || input.environment.values().any(|value| value.contains('\0'))
+    {
+        return Err(Status::invalid_argument(
+            "environment variable names must be non-empty and contain no '=' or NUL bytes, and values no NUL bytes",
+        ));
+    }
+    if let Some(limits) = &input.limits {
+        validate_limits(limits)?;
+    }
+    if input.timeout_ms == Some(0) || input.idle_timeout_ms == Some(0) {
+        return Err(Status::invalid_argument("timeouts must be non-zero"));
+    }
+    winebridge::PriorityClass::try_from(input.priority)
+        .map_err(|_| Status::invalid_argument("invalid priority class"))?;
+    if let Some(mask) = input.affinity_mask {
+        validate_affinity(mask)?;
+    }
+    winebridge::LaunchLifecycle::try_from(input.lifecycle)
+        .map_err(|_| Status::invalid_argument("invalid lifecycle policy"))?;
+    hooks::validate(&input.pre_launch)?;
+    hooks::validate(&input.post_exit)?;
+    match winebridge::LaunchMode::try_from(input.mode)
+        .map_err(|_| Status::invalid_argument("invalid launch mode"))?
+    {
+        winebridge::LaunchMode::Unspecified | winebridge::LaunchMode::CreateProcess => {
+            if input.verb.is_some() {
+                return Err(Status::invalid_argument(
+                    "a verb is only supported by shell launches",
+                ));
+            }
+        }
+        winebridge::LaunchMode::ShellExecute => {
+            if input
+                .verb
+                .as_deref()
+                .is_some_and(|verb| verb.contains('\0'))
+            {
+                return Err(Status::invalid_argument("verb must contain no NUL bytes"));
+            }
+            if input.redirect_stdin
+                || input.clean_environment
+                || !input.environment.is_empty()
+                || !input.unset_environment.is_empty()
+            {
+                return Err(Status::invalid_argument(
+                    "shell launches support neither stdin redirection nor environment changes",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks an affinity mask, which cannot name more CPUs than a pointer has
+/// bits, so 32 on 32-bit Windows.
+fn validate_affinity(mask: u64) -> Result<(), Status> {
+    if mask == 0 {
+        return Err(Status::invalid_argument("affinity mask must be non-zero"));
+    }
+    if usize::try_from(mask).is_err() {
+        return Err(Status::invalid_argument(
+            "affinity mask names more CPUs than this system supports",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_limits(limits: &winebridge::ProcessLimits) -> Result<(), Status> {
+    if limits.job_memory_bytes == Some(0) || limits.process_memory_bytes == Some(0) {
+        return Err(Status::invalid_argument("memory limits must be non-zero"));
+    }
+    if [limits.job_memory_bytes, limits.process_memory_bytes]
+        .into_iter()
+        .flatten()
+        .any(|bytes| usize::try_from(bytes).is_err())
+    {
+        return Err(Status::invalid_argument(
+            "memory limits must fit in this system's address space",
+        ));
+    }
+    if limits.active_processes == Some(0) {
+        return Err(Status::invalid_argument(
+            "active process limit must be non-zero",
+        ));
+    }
+    if limits
+        .cpu_rate_percent
+        .is_some_and(|percent| !(1..=100).contains(&percent))
+    {
+        return Err(Status::invalid_argument(
+            "CPU rate limit must be between 1 and 100 percent",
+        ));
+    }
+    Ok(())
+}
+
+fn console_size(size: winebridge::ConsoleSize) -> Result<(u16, u16), Status> {
+    let limit = 1..=i16::MAX as u32;
+    if !limit.contains(&size.columns) || !limit.contains(&size.rows) {
+        return Err(Status::invalid_argument(format!(
+            "console size must be between 1 and {} cells in each direction",
+            i16::MAX
+        )));
+    }
+    Ok((size.columns as u16, size.rows as u16))
+}
+
+fn process_target(
+    target: Option<winebridge::ProcessTarget>,
+) -> Result<winebridge::process_target::Target, Status> {
+    match target.and_then(|target| target.target) {
+        None => Err(Status::invalid_argument("process target is required")),
+        Some(winebridge::process_target::Target::Id(id)) => {
+            required(&id, "program id")?;
+            Ok(winebridge::process_target::Target::Id(id))
+        }
+        Some(target) => Ok(target),
+    }
+}
+
 fn wineboot_args(value: i32) -> Result<&'static str, Status> {
     match winebridge::WinebootMode::try_from(value)
         .map_err(|_| Status::invalid_argument("invalid wineboot mode"))?
@@ -79,16 +288,56 @@ fn wineboot_args(value: i32) -> Result<&'static str, Status> {
 
 pub struct WineBridgeService {
     shutdown_signal: Mutex<Option<oneshot::Sender<()>>>,
+    processes: Arc<ProcessManager>,
 }
 
 impl WineBridgeService {
     pub fn new(shutdown_signal: oneshot::Sender<()>) -> Self {
         Self {
             shutdown_signal: Mutex::new(Some(shutdown_signal)),
+            processes: Arc::new(ProcessManager::new()),
         }
     }
 }
 
+impl WineBridgeService {
+    /// Starts `launch` with `start` on the blocking pool, after reserving its
+    /// id and running its pre-launch actions. `start` hands back the launch
+    /// it registered, if any, whose post-exit actions then run on their own
+    /// once its processes have exited, or right away if it never started, so
+    /// whatever the pre-launch actions changed is restored either way.
+    async fn launch_with_hooks<T: Send + 'static>(
+        &self,
+        mut launch: winebridge::LaunchProcessRequest,
+        start: impl FnOnce(
+            &ProcessManager,
+            Reservation<'_>,
+            winebridge::LaunchProcessRequest,
+        ) -> windows::core::Result<(T, Option<Arc<Launch>>)>
+        + Send
+        + 'static,
+    ) -> Result<T> {
+        let processes = self.processes.clone();
+        tokio::task::spawn_blocking(move || {
+            let id = launch.id.clone();
+            let pre_launch = std::mem::take(&mut launch.pre_launch);
+            let post_exit = std::mem::take(&mut launch.post_exit);
+
+            let reservation = processes.reserve(&launch).map_err(status::windows)?;
+            let started = hooks::run(&processes, &pre_launch)
+                .and_then(|()| start(&processes, reservation, launch).map_err(status::windows));
+            let (started, started_launch) = match started {
+                Ok((started, launch)) => (Ok(started), launch),
+                Err(error) => (Err(error), None),
+            };
+            hooks::run_after_exit(processes, started_launch, id, post_exit);
+            started
+        })
+        .await
+        .map_err(|error| Status::internal(error.to_string()))?
+    }
+}
+
 #[tonic::async_trait]
 impl WineBridge for WineBridgeService {
     // --- Process Management ---
@@ -97,52 +346,367 @@ impl WineBridge for WineBridgeService {
         &self,
         _request: Request<()>,
     ) -> Result<Response<winebridge::ListProcessesResponse>> {
-        let processes = ProcessManager
-            .running_processes()
-            .map_err(status::windows)?;
+        let manager = self.processes.clone();
+        let processes = tokio::task::spawn_blocking(move || {
+            let running = manager.running_processes()?;
+            let launch_ids = manager.launch_ids();
+
+            Ok::<Vec<_>, windows::core::Error>(
+                running
+                    .iter()
+                    .map(|process| {
+                        // Inaccessible and already-exited processes are still listed,
+                        // just without the details that need a handle.
+                        let handle = ProcessHandle::query(process.pid()).ok();
+                        winebridge::Process {
+                            name: process.name(),
+                            pid: process.pid(),
+                            threads: process.thread_count(),
+                            parent_pid: process.parent_pid(),
+                            image_path: handle.as_ref().and_then(|handle| handle.image_path().ok()),
+                            wow64: handle.as_ref().and_then(|handle| handle.is_wow64().ok()),
+                            stats: handle
+                                .as_ref()
+                                .and_then(|handle| handle.stats().ok())
+                                .as_ref()
+                                .map(processes::stats_to_proto),
+                            launch_id: launch_ids.get(&process.pid()).cloned(),
+                        }
+                    })
+                    .collect(),
+            )
+        })
+        .await
+        .map_err(|error| Status::internal(error.to_string()))?
+        .map_err(status::windows)?;
 
-        let processes = processes
+        Ok(Response::new(winebridge::ListProcessesResponse {
+            processes,
+        }))
+    }
+
+    async fn list_process_threads(
+        &self,
+        request: Request<winebridge::ListProcessThreadsRequest>,
+    ) -> Result<Response<winebridge::ListProcessThreadsResponse>> {
+        let pid = request.into_inner().pid;
+        let threads = self
+            .processes
+            .threads(pid)
+            .map_err(status::windows)?
             .iter()
-            .map(|process| winebridge::Process {
-                name: process.name(),
-                pid: process.pid(),
-                threads: process.thread_count(),
+            .map(|thread| {
+                // Threads that already exited, or deny access, are still
+                // listed, just without their times.
+                let times = ThreadHandle::open(thread.tid(), THREAD_QUERY_LIMITED_INFORMATION)
+                    .and_then(|handle| handle.times())
+                    .ok();
+                winebridge::ProcessThread {
+                    tid: thread.tid(),
+                    base_priority: thread.base_priority(),
+                    created_unix_ms: times.as_ref().and_then(|times| {
+                        let since_epoch = times.created.duration_since(UNIX_EPOCH).ok()?;
+                        Some(since_epoch.as_millis() as u64)
+                    }),
+                    kernel_time_ms: times
+                        .as_ref()
+                        .map(|times| times.kernel_time.as_millis() as u64),
+                    user_time_ms: times
+                        .as_ref()
+                        .map(|times| times.user_time.as_millis() as u64),
+                }
             })
             .collect();
 
-        Ok(Response::new(winebridge::ListProcessesResponse {
-            processes,
+        Ok(Response::new(winebridge::ListProcessThreadsResponse {
+            threads,
+        }))
+    }
+
+    async fn list_process_modules(
+        &self,
+        request: Request<winebridge::ListProcessModulesRequest>,
+    ) -> Result<Response<winebridge::ListProcessModulesResponse>> {
+        let pid = request.into_inner().pid;
+        let modules = self
+            .processes
+            .modules(pid)
+            .map_err(status::windows)?
+            .iter()
+            .map(|module| winebridge::ProcessModule {
+                name: module.name(),
+                path: module.path(),
+                base_address: module.base_address(),
+                size: module.size(),
+            })
+            .collect();
+
+        Ok(Response::new(winebridge::ListProcessModulesResponse {
+            modules,
+        }))
+    }
+
+    async fn get_process_stacks(
+        &self,
+        request: Request<winebridge::GetProcessStacksRequest>,
+    ) -> Result<Response<winebridge::GetProcessStacksResponse>> {
+        let input = request.into_inner();
+        let max_frames = input.max_frames.unwrap_or(DEFAULT_STACK_FRAMES);
+        if max_frames == 0 || max_frames > MAX_STACK_FRAMES {
+            return Err(Status::invalid_argument(format!(
+                "frame limit must be between 1 and {MAX_STACK_FRAMES}"
+            )));
+        }
+
+        let processes = self.processes.clone();
+        let stacks =
+            tokio::task::spawn_blocking(move || processes.stacks(input.pid, max_frames as usize))
+                .await
+                .map_err(|error| Status::internal(error.to_string()))?
+                .map_err(status::windows)?;
+
+        Ok(Response::new(winebridge::GetProcessStacksResponse {
+            threads: stacks.iter().map(processes::stack_to_proto).collect(),
         }))
     }
 
+    type MonitorProcessesStream = ReceiverStream<winebridge::ProcessSample>;
+
+    async fn monitor_processes(
+        &self,
+        request: Request<winebridge::MonitorProcessesRequest>,
+    ) -> Result<Response<Self::MonitorProcessesStream>> {
+        let interval = Duration::from_millis(request.into_inner().interval_ms.into());
+        if interval < MIN_MONITOR_INTERVAL {
+            return Err(Status::invalid_argument(format!(
+                "monitor interval must be at least {} ms",
+                MIN_MONITOR_INTERVAL.as_millis()
+            )));
+        }
+
+        let (sender, receiver) = mpsc::channel(1);
+        self.processes.monitor(interval, sender);
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
     async fn launch_process(
         &self,
         request: Request<winebridge::LaunchProcessRequest>,
     ) -> Result<Response<winebridge::LaunchProcessResponse>> {
         let input = request.into_inner();
-        if input.id.is_empty()
-            || input.id.contains('\0')
-            || input.executable.is_empty()
-            || input.executable.contains('\0')
-            || input
-                .arguments
-                .iter()
-                .any(|argument| argument.contains('\0'))
-            || input
-                .working_directory
-                .as_deref()
-                .is_some_and(|directory| directory.contains('\0'))
-        {
+        validate_launch(&input)?;
+        // The child's stdout and stderr could only be left null next to a
+        // redirected stdin, hiding the very prompt the client should answer.
+        if input.redirect_stdin {
             return Err(Status::invalid_argument(
-                "process paths and arguments must be non-empty where required and contain no NUL bytes",
+                "stdin can only be redirected for launches whose output is captured",
             ));
         }
 
-        let pid = ProcessManager.execute(input).map_err(status::windows)?;
+        let pid = self
+            .launch_with_hooks(input, |processes, reservation, input| {
+                let launch = processes.execute(reservation, input)?;
+                Ok((
+                    launch.as_ref().map_or(0, |launch| launch.process.pid()),
+                    launch,
+                ))
+            })
+            .await?;
 
         Ok(Response::new(winebridge::LaunchProcessResponse { pid }))
     }
 
+    type LaunchProcessWithOutputStream = ReceiverStream<winebridge::ProcessOutput>;
+
+    async fn launch_process_with_output(
+        &self,
+        request: Request<winebridge::LaunchProcessRequest>,
+    ) -> Result<Response<Self::LaunchProcessWithOutputStream>> {
+        let input = request.into_inner();
+        validate_launch(&input)?;
+        if input.mode() == winebridge::LaunchMode::ShellExecute {
+            return Err(Status::invalid_argument(
+                "output cannot be captured from shell launches",
+            ));
+        }
+
+        let (sender, receiver) = mpsc::channel(OUTPUT_EVENT_BUFFER);
+        self.launch_with_hooks(input, |processes, reservation, input| {
+            let launch = processes.execute_with_output(reservation, input, sender)?;
+            Ok(((), Some(launch)))
+        })
+        .await?;
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn run_command(
+        &self,
+        request: Request<winebridge::RunCommandRequest>,
+    ) -> Result<Response<winebridge::RunCommandResponse>> {
+        let input = request.into_inner();
+        let launch = input
+            .launch
+            .ok_or_else(|| Status::invalid_argument("command launch is required"))?;
+        validate_launch(&launch)?;
+        if launch.mode() == winebridge::LaunchMode::ShellExecute {
+            return Err(Status::invalid_argument(
+                "output cannot be captured from shell launches",
+            ));
+        }
+        if input.timeout_ms == Some(0) {
+            return Err(Status::invalid_argument("timeouts must be non-zero"));
+        }
+        let timeout = input.timeout_ms.map(|ms| Duration::from_millis(ms.into()));
+        let output_limit = input.output_limit_bytes.unwrap_or(DEFAULT_RUN_OUTPUT_LIMIT);
+        if output_limit > MAX_RUN_OUTPUT_LIMIT {
+            return Err(Status::invalid_argument(format!(
+                "output limit must be at most {MAX_RUN_OUTPUT_LIMIT} bytes"
+            )));
+        }
+
+        let output = self
+            .launch_with_hooks(launch, move |processes, reservation, launch| {
+                // The launch has exited by the time `run` returns.
+                let output = processes.run(reservation, launch, timeout, output_limit as usize)?;
+                Ok((output, None))
+            })
+            .await?;
+
+        Ok(Response::new(winebridge::RunCommandResponse {
+            exit_code: output.exit.exit_code,
+            duration_ms: output.exit.duration.as_millis() as u64,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            truncated: output.truncated,
+            timed_out: output.timed_out,
+        }))
+    }
+
+    type OpenConsoleStream = ReceiverStream<winebridge::ConsoleOutput>;
+
+    async fn open_console(
+        &self,
+        request: Request<Streaming<winebridge::ConsoleInput>>,
+    ) -> Result<Response<Self::OpenConsoleStream>> {
+        let mut messages = request.into_inner();
+        let Some(Input::Start(start)) = messages.message().await?.and_then(|input| input.input)
+        else {
+            return Err(Status::invalid_argument(
+                "the first console message must start the session",
+            ));
+        };
+        let launch = start
+            .launch
+            .ok_or_else(|| Status::invalid_argument("console launch is required"))?;
+        validate_launch(&launch)?;
+        if launch.mode() == winebridge::LaunchMode::ShellExecute || launch.redirect_stdin {
+            return Err(Status::invalid_argument(
+                "console sessions support neither shell launches nor stdin redirection",
+            ));
+        }
+        let (columns, rows) = console_size(start.size.unwrap_or(DEFAULT_CONSOLE_SIZE))?;
+
+        let (sender, receiver) = mpsc::channel(OUTPUT_EVENT_BUFFER);
+        let session = self
+            .launch_with_hooks(launch, move |processes, reservation, launch| {
+                let session = processes.open_console(reservation, launch, columns, rows, sender)?;
+                let launch = session.launch.clone();
+                Ok((session, Some(launch)))
+            })
+            .await?;
+
+        tokio::spawn(async move {
+            loop {
+                let input = match messages.message().await {
+                    Ok(Some(message)) => message.input,
+                    Ok(None) => break,
+                    Err(error) => {
+                        tracing::debug!("Console input stream failed: {error}");
+                        break;
+                    }
+                };
+                match input {
+                    Some(Input::Data(data)) => {
+                        let session = session.clone();
+                        let written =
+                            tokio::task::spawn_blocking(move || session.write(&data)).await;
+                        if !matches!(written, Ok(Ok(()))) {
+                            tracing::debug!("Console input closed by the program");
+                            break;
+                        }
+                    }
+                    Some(Input::Resize(size)) => {
+                        let resized = console_size(size).and_then(|(columns, rows)| {
+                            session.resize(columns, rows).map_err(status::windows)
+                        });
+                        if let Err(error) = resized {
+                            tracing::debug!("Failed to resize console: {}", error.message());
+                        }
+                    }
+                    Some(Input::Start(_)) | None => {}
+                }
+            }
+            session.close_input();
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn wait_process(
+        &self,
+        request: Request<winebridge::WaitProcessRequest>,
+    ) -> Result<Response<winebridge::WaitProcessResponse>> {
+        let input = request.into_inner();
+        required(&input.id, "program id")?;
+        let timeout = input.timeout_ms.map(|ms| Duration::from_millis(ms.into()));
+
+        let processes = self.processes.clone();
+        let exit = tokio::task::spawn_blocking(move || processes.wait(&input.id, timeout))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(winebridge::WaitProcessResponse {
+            exit_code: exit.exit_code,
+            duration_ms: exit.duration.as_millis() as u64,
+            crash: exit.crash.map(|crash| winebridge::ProcessCrash {
+                exception_code: crash.exception_code,
+                dump_path: crash
+                    .dump_path
+                    .map(|path| path.to_string_lossy().into_owned()),
+            }),
+            watchdog: processes::watchdog_to_proto(exit.watchdog) as i32,
+        }))
+    }
+
+    async fn write_process_stdin(
+        &self,
+        request: Request<Streaming<winebridge::ProcessStdinRequest>>,
+    ) -> Result<Response<()>> {
+        let mut messages = request.into_inner();
+        while let Some(input) = messages.message().await? {
+            required(&input.id, "program id")?;
+
+            let processes = self.processes.clone();
+            tokio::task::spawn_blocking(move || {
+                if !input.data.is_empty() {
+                    processes.write_stdin(&input.id, &input.data)?;
+                }
+                if input.close {
+                    processes.close_stdin(&input.id)?;
+                }
+                Ok::<_, windows::core::Error>(())
+            })
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+        }
+
+        Ok(Response::new(()))
+    }
+
     async fn kill_process(
         &self,
         request: Request<winebridge::KillProcessRequest>,
@@ -153,7 +717,205 @@ impl WineBridge for WineBridgeService {
                 "program id must be non-empty and contain no NUL bytes",
             ));
         }
-        ProcessManager.kill(id).map_err(status::windows)?;
+        let processes = self.processes.clone();
+        let id = id.clone();
+        tokio::task::spawn_blocking(move || processes.kill(&id))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn stop_process(
+        &self,
+        request: Request<winebridge::StopProcessRequest>,
+    ) -> Result<Response<winebridge::StopProcessResponse>> {
+        let input = request.into_inner();
+        required(&input.id, "program id")?;
+        let grace_period = Duration::from_millis(input.grace_period_ms.into());
+
+        let processes = self.processes.clone();
+        let report = tokio::task::spawn_blocking(move || processes.stop(&input.id, grace_period))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(winebridge::StopProcessResponse {
+            exited: report.exited,
+            killed: report.killed,
+        }))
+    }
+
+    async fn list_launches(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<winebridge::ListLaunchesResponse>> {
+        let processes = self.processes.clone();
+        let launches = tokio::task::spawn_blocking(move || {
+            let recovered = processes
+                .recovered()
+                .into_iter()
+                .map(|(id, recovered)| processes::recovered_to_proto(id, &recovered));
+            processes
+                .launches()
+                .into_iter()
+                .map(|(id, launch)| processes::launch_to_proto(id, &launch))
+                .chain(recovered)
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|error| Status::internal(error.to_string()))?;
+
+        Ok(Response::new(winebridge::ListLaunchesResponse { launches }))
+    }
+
+    async fn get_launch(
+        &self,
+        request: Request<winebridge::GetLaunchRequest>,
+    ) -> Result<Response<winebridge::LaunchInfo>> {
+        let id = request.into_inner().id;
+        required(&id, "program id")?;
+
+        let processes = self.processes.clone();
+        let info = tokio::task::spawn_blocking(move || match processes.launch(&id) {
+            Ok(launch) => Ok(processes::launch_to_proto(id, &launch)),
+            Err(_) => processes
+                .recovered_launch(&id)
+                .map(|recovered| processes::recovered_to_proto(id, &recovered)),
+        })
+        .await
+        .map_err(|error| Status::internal(error.to_string()))?
+        .map_err(status::windows)?;
+
+        Ok(Response::new(info))
+    }
+
+    async fn get_launch_accounting(
+        &self,
+        request: Request<winebridge::GetLaunchAccountingRequest>,
+    ) -> Result<Response<winebridge::GetLaunchAccountingResponse>> {
+        let id = request.into_inner().id;
+        required(&id, "program id")?;
+
+        let processes = self.processes.clone();
+        let runs = tokio::task::spawn_blocking(move || processes.accounting(&id))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+        let runs: Vec<_> = runs.iter().map(processes::run_to_proto).collect();
+
+        Ok(Response::new(winebridge::GetLaunchAccountingResponse {
+            total_wall_time_ms: runs.iter().map(|run| run.wall_time_ms).sum(),
+            total_cpu_time_ms: runs
+                .iter()
+                .map(|run| run.user_time_ms + run.kernel_time_ms)
+                .sum(),
+            peak_memory_bytes: runs
+                .iter()
+                .map(|run| run.peak_memory_bytes)
+                .max()
+                .unwrap_or(0),
+            runs,
+        }))
+    }
+
+    type WatchProcessEventsStream = ReceiverStream<winebridge::ProcessEvent>;
+
+    async fn watch_process_events(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Self::WatchProcessEventsStream>> {
+        let mut events = self
+            .processes
+            .events()
+            .map_err(status::windows)?
+            .subscribe();
+        let (sender, receiver) = mpsc::channel(OUTPUT_EVENT_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Process event subscriber missed {missed} events");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                if sender.send(event).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn suspend_process(
+        &self,
+        request: Request<winebridge::SuspendProcessRequest>,
+    ) -> Result<Response<()>> {
+        let input = request.into_inner();
+        required(&input.id, "program id")?;
+
+        let processes = self.processes.clone();
+        tokio::task::spawn_blocking(move || processes.suspend(&input.id))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn resume_process(
+        &self,
+        request: Request<winebridge::ResumeProcessRequest>,
+    ) -> Result<Response<()>> {
+        let input = request.into_inner();
+        required(&input.id, "program id")?;
+
+        let processes = self.processes.clone();
+        tokio::task::spawn_blocking(move || processes.resume(&input.id))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn set_process_priority(
+        &self,
+        request: Request<winebridge::SetProcessPriorityRequest>,
+    ) -> Result<Response<()>> {
+        let input = request.into_inner();
+        let target = process_target(input.target)?;
+        let priority = winebridge::PriorityClass::try_from(input.priority)
+            .map_err(|_| Status::invalid_argument("invalid priority class"))?;
+        let class = processes::priority_class(priority)
+            .ok_or_else(|| Status::invalid_argument("priority class is required"))?;
+
+        let processes = self.processes.clone();
+        tokio::task::spawn_blocking(move || processes.set_priority(&target, class))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn set_process_affinity(
+        &self,
+        request: Request<winebridge::SetProcessAffinityRequest>,
+    ) -> Result<Response<()>> {
+        let input = request.into_inner();
+        let target = process_target(input.target)?;
+        validate_affinity(input.affinity_mask)?;
+
+        let processes = self.processes.clone();
+        tokio::task::spawn_blocking(move || processes.set_affinity(&target, input.affinity_mask))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))?
+            .map_err(status::windows)?;
 
         Ok(Response::new(()))
     }
@@ -439,6 +1201,61 @@ impl WineBridge for WineBridgeService {
         Ok(Response::new(()))
     }
 
+    // --- Windows ---
+
+    async fn list_windows(
+        &self,
+        request: Request<winebridge::ListWindowsRequest>,
+    ) -> Result<Response<winebridge::ListWindowsResponse>> {
+        let pid = request.into_inner().pid;
+        let windows = WindowManager
+            .list()
+            .map_err(status::windows)?
+            .iter()
+            .filter(|window| pid.is_none_or(|pid| window.pid == pid))
+            .map(|window| winebridge::Window {
+                handle: window.handle.0 as usize as u64,
+                title: window.title(),
+                class_name: window.class_name(),
+                pid: window.pid,
+                visible: window.is_visible(),
+                minimized: window.is_minimized(),
+                rect: window.rect().ok().map(|rect| winebridge::WindowRect {
+                    left: rect.left,
+                    top: rect.top,
+                    right: rect.right,
+                    bottom: rect.bottom,
+                }),
+            })
+            .collect();
+
+        Ok(Response::new(winebridge::ListWindowsResponse { windows }))
+    }
+
+    async fn window_action(
+        &self,
+        request: Request<winebridge::WindowActionRequest>,
+    ) -> Result<Response<()>> {
+        let input = request.into_inner();
+        let action = winebridge::WindowAction::try_from(input.action)
+            .map_err(|_| Status::invalid_argument("invalid window action"))?;
+        // Resolved before the window, so a bad action is reported as such
+        // rather than as a missing window.
+        let apply = match action {
+            winebridge::WindowAction::Unspecified => {
+                return Err(Status::invalid_argument("window action is required"));
+            }
+            winebridge::WindowAction::Close => WindowManager::close,
+            winebridge::WindowAction::Minimize => WindowManager::minimize,
+            winebridge::WindowAction::Restore => WindowManager::restore,
+            winebridge::WindowAction::Foreground => WindowManager::foreground,
+        };
+        let window = WindowManager.get(input.handle).map_err(status::windows)?;
+        apply(&WindowManager, &window).map_err(status::windows)?;
+
+        Ok(Response::new(()))
+    }
+
     // --- System ---
 
     async fn shutdown(&self, _request: Request<()>) -> Result<Response<()>> {
@@ -573,6 +1390,115 @@ mod tests {
         std::fs::remove_dir_all(directory).unwrap();
     }
 
+    /// A service whose launches stay out of the prefix's launch table and
+    /// history.
+    fn service(name: &str) -> WineBridgeService {
+        WineBridgeService {
+            shutdown_signal: Mutex::new(None),
+            processes: Arc::new(processes::testing::manager(name)),
+        }
+    }
+
+    /// A launch of system program `program` as `id` with a raw command line,
+    /// which dies with the bridge.
+    fn launch(id: &str, program: &str, command_line: &str) -> winebridge::LaunchProcessRequest {
+        winebridge::LaunchProcessRequest {
+            id: id.to_string(),
+            executable: processes::testing::system_program(program),
+            command_line: Some(command_line.to_string()),
+            lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_process_reports_the_exit_code_and_run_time() {
+        let service = service("wait");
+        let id = processes::testing::unique_name("wait");
+        service
+            .launch_process(Request::new(launch(&id, "cmd", "/c exit 3")))
+            .await
+            .unwrap();
+        let wait = || {
+            service.wait_process(Request::new(winebridge::WaitProcessRequest {
+                id: id.clone(),
+                timeout_ms: Some(10_000),
+            }))
+        };
+
+        let first = wait().await.unwrap().into_inner();
+        assert_eq!(first.exit_code, 3);
+        assert!(first.duration_ms < 10_000);
+
+        // A launch that ended long ago still reports how long it ran.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let second = wait().await.unwrap().into_inner();
+        assert_eq!(second.exit_code, 3);
+        assert_eq!(second.duration_ms, first.duration_ms);
+    }
+
+    #[tokio::test]
+    async fn run_command_captures_both_streams_and_the_exit_code() {
+        let service = service("run");
+        let id = processes::testing::unique_name("run");
+        let output = service
+            .run_command(Request::new(winebridge::RunCommandRequest {
+                launch: Some(launch(&id, "cmd", "/c echo out& echo err 1>&2& exit 4")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(output.exit_code, 4);
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "out");
+        assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "err");
+        assert!(!output.truncated);
+        assert!(!output.timed_out);
+    }
+
+    #[tokio::test]
+    async fn run_command_truncates_output_at_the_limit() {
+        let service = service("run-truncate");
+        let id = processes::testing::unique_name("run-truncate");
+        let output = service
+            .run_command(Request::new(winebridge::RunCommandRequest {
+                launch: Some(launch(&id, "cmd", "/c echo 0123456789")),
+                output_limit_bytes: Some(4),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(output.stdout, b"0123");
+        assert!(output.truncated);
+    }
+
+    #[tokio::test]
+    async fn run_command_terminates_commands_past_the_timeout() {
+        let service = service("run-timeout");
+        let id = processes::testing::unique_name("run-timeout");
+        let run = |timeout_ms| {
+            service.run_command(Request::new(winebridge::RunCommandRequest {
+                // Runs until its window is closed.
+                launch: Some(launch(&id, "notepad", "")),
+                timeout_ms: Some(timeout_ms),
+                ..Default::default()
+            }))
+        };
+
+        assert_eq!(
+            run(0).await.err().map(|status| status.code()),
+            Some(tonic::Code::InvalidArgument)
+        );
+        let output = run(500).await.unwrap().into_inner();
+        assert!(output.timed_out);
+        assert_eq!(output.exit_code, processes::watchdog::WATCHDOG_EXIT_CODE);
+        assert!(output.duration_ms < 10_000);
+    }
+
     #[test]
     fn validates_wineboot_modes() {
         assert!(wineboot_args(0).is_err());
@@ -582,4 +1508,106 @@ mod tests {
         );
         assert!(wineboot_args(i32::MAX).is_err());
     }
+
+    #[test]
+    fn rejects_quoted_executables_of_launches_and_actions() {
+        let action = |executable: &str| winebridge::LaunchAction {
+            action: Some(winebridge::launch_action::Action::RunCommand(
+                winebridge::RunCommandAction {
+                    executable: executable.to_string(),
+                    ..Default::default()
+                },
+            )),
+        };
+        assert!(hooks::validate(&[action(r"C:\windows\regedit.exe")]).is_ok());
+        assert!(hooks::validate(&[action(r#"C:\windows\regedit.exe" /s"#)]).is_err());
+        assert!(
+            validate_launch(&winebridge::LaunchProcessRequest {
+                id: "game".to_string(),
+                executable: r#"C:\game.exe" -x"#.to_string(),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_registry_actions_with_invalid_addresses() {
+        use winebridge::registry_value::Value as ProtoValue;
+
+        let hive = winebridge::RegistryHive::CurrentUser as i32;
+        let set = |subkey: &str, name: &str, value| winebridge::LaunchAction {
+            action: Some(winebridge::launch_action::Action::SetRegistryValue(
+                winebridge::SetRegistryValueRequest {
+                    hive,
+                    subkey: subkey.to_string(),
+                    name: name.to_string(),
+                    value: Some(winebridge::RegistryValue { value: Some(value) }),
+                },
+            )),
+        };
+        let delete = |hive, subkey: &str| winebridge::LaunchAction {
+            action: Some(winebridge::launch_action::Action::DeleteRegistryValue(
+                winebridge::RegistryValueRequest {
+                    hive,
+                    subkey: subkey.to_string(),
+                    name: "value".to_string(),
+                },
+            )),
+        };
+        let dword = || ProtoValue::Dword(1);
+
+        assert!(hooks::validate(&[set(r"Software\Game", "value", dword())]).is_ok());
+        assert!(hooks::validate(&[set("", "value", dword())]).is_err());
+        assert!(hooks::validate(&[set(r"Software\Game", "bad\0name", dword())]).is_err());
+        assert!(
+            hooks::validate(&[set(
+                r"Software\Game",
+                "value",
+                ProtoValue::String("bad\0value".to_string())
+            )])
+            .is_err()
+        );
+        assert!(hooks::validate(&[delete(hive, r"Software\Game")]).is_ok());
+        assert!(hooks::validate(&[delete(0, r"Software\Game")]).is_err());
+        assert!(hooks::validate(&[delete(hive, "Software\\Game\0")]).is_err());
+    }
+
+    #[test]
+    fn validates_process_limits() {
+        let limits = |cpu_rate_percent| winebridge::ProcessLimits {
+            cpu_rate_percent,
+            ..Default::default()
+        };
+        assert!(validate_limits(&limits(None)).is_ok());
+        assert!(validate_limits(&limits(Some(100))).is_ok());
+        assert!(validate_limits(&limits(Some(0))).is_err());
+        assert!(validate_limits(&limits(Some(101))).is_err());
+        assert!(
+            validate_limits(&winebridge::ProcessLimits {
+                job_memory_bytes: Some(0),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        if usize::BITS < u64::BITS {
+            assert!(
+                validate_limits(&winebridge::ProcessLimits {
+                    process_memory_bytes: Some(u64::MAX),
+                    ..Default::default()
+                })
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn validates_affinity_masks() {
+        assert!(validate_affinity(1).is_ok());
+        assert!(validate_affinity(0).is_err());
+        assert_eq!(
+            validate_affinity(u64::MAX).is_ok(),
+            usize::BITS == u64::BITS
+        );
+    }
 }
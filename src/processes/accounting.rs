@@ -0,0 +1,216 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use super::job::JobAccounting;
+use super::launch::Launch;
+use super::state::{self, escape, unescape};
+
+/// History file, inside [`state::directory`].
+pub const HISTORY_FILE: &str = "launch-accounting.tsv";
+
+/// Resources used by one run of a launch id, from its start until its job
+/// emptied. Every run has a job of its own, so its job's counters and peak
+/// memory are the run's alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub id: String,
+    pub started_at: SystemTime,
+    pub wall_time: Duration,
+    pub usage: JobAccounting,
+}
+
+impl Run {
+    /// The run so far of `launch`, which may still be going.
+    pub fn of(id: String, launch: &Launch) -> Result<Self, windows::core::Error> {
+        Ok(Self {
+            id,
+            started_at: launch.started_at,
+            wall_time: launch.run_time(),
+            usage: launch.job.accounting()?,
+        })
+    }
+
+    /// One line of the history file: tab-separated numbers, in milliseconds
+    /// and bytes, followed by the escaped launch id.
+    fn to_line(&self) -> String {
+        let started = self
+            .started_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            started.as_millis(),
+            self.wall_time.as_millis(),
+            self.usage.user_time.as_millis(),
+            self.usage.kernel_time.as_millis(),
+            self.usage.peak_memory,
+            self.usage.read_bytes,
+            self.usage.write_bytes,
+            self.usage.other_bytes,
+            escape(&self.id),
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(9, '\t');
+        let mut number = || fields.next()?.parse::<u64>().ok();
+        let started = number()?;
+        let wall_time = number()?;
+        let user_time = number()?;
+        let kernel_time = number()?;
+        let peak_memory = number()?;
+        let read_bytes = number()?;
+        let write_bytes = number()?;
+        let other_bytes = number()?;
+        let id = unescape(fields.next()?)?;
+
+        Some(Self {
+            id,
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_millis(started),
+            wall_time: Duration::from_millis(wall_time),
+            usage: JobAccounting {
+                user_time: Duration::from_millis(user_time),
+                kernel_time: Duration::from_millis(kernel_time),
+                peak_memory,
+                read_bytes,
+                write_bytes,
+                other_bytes,
+            },
+        })
+    }
+}
+
+/// Finished runs of every launch id, appended to a file inside the prefix so
+/// they outlive the bridge.
+pub struct History {
+    path: PathBuf,
+    /// Serializes appends, so concurrent runs never interleave their lines.
+    lock: Mutex<()>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(state::directory().join(HISTORY_FILE))
+    }
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::default(),
+        }
+    }
+
+    pub fn append(&self, run: &Run) -> io::Result<()> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(run.to_line().as_bytes())
+    }
+
+    /// Every recorded run of launch `id`, oldest first. Lines that cannot be
+    /// parsed, such as one cut short by a crash, are skipped.
+    pub fn runs(&self, id: &str) -> io::Result<Vec<Run>> {
+        let contents = {
+            let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            match fs::read_to_string(&self.path) {
+                Ok(contents) => contents,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(error) => return Err(error),
+            }
+        };
+        Ok(contents
+            .lines()
+            .filter_map(Run::from_line)
+            .filter(|run| run.id == id)
+            .collect())
+    }
+}
+
+/// Records the run of `launch`, whose job has emptied, into `history`.
+pub fn record(launch: &Launch, id: &str, history: &History) {
+    let recorded = Run::of(id.to_string(), launch)
+        .map_err(io::Error::from)
+        .and_then(|run| history.append(&run));
+    if let Err(error) = recorded {
+        tracing::warn!("Failed to record the accounting of launch {id}: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_history_lines() {
+        let run = Run {
+            id: "game\twith\\odd\nid".to_string(),
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+            wall_time: Duration::from_millis(3_600_000),
+            usage: JobAccounting {
+                user_time: Duration::from_millis(1_500),
+                kernel_time: Duration::from_millis(250),
+                peak_memory: 512 * 1024 * 1024,
+                read_bytes: 1,
+                write_bytes: 2,
+                other_bytes: 3,
+            },
+        };
+        let line = run.to_line();
+
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(Run::from_line(line.trim_end_matches('\n')), Some(run));
+    }
+
+    #[test]
+    fn records_runs_of_each_id_separately() {
+        let directory = std::env::temp_dir().join(format!(
+            "bottles-winebridge-accounting-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let history = History::new(directory.join(HISTORY_FILE));
+        let run = |id: &str, seconds| Run {
+            id: id.to_string(),
+            started_at: SystemTime::UNIX_EPOCH,
+            wall_time: Duration::from_secs(60),
+            usage: JobAccounting {
+                user_time: Duration::from_secs(seconds),
+                kernel_time: Duration::from_secs(seconds),
+                peak_memory: seconds * 4096,
+                read_bytes: seconds * 100,
+                write_bytes: seconds * 100,
+                other_bytes: seconds * 100,
+            },
+        };
+
+        history.append(&run("game", 2)).unwrap();
+        history.append(&run("other", 5)).unwrap();
+        history.append(&run("game", 3)).unwrap();
+
+        assert_eq!(
+            history.runs("game").unwrap(),
+            [run("game", 2), run("game", 3)]
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert_eq!(Run::from_line("1\t2\t3"), None);
+        assert_eq!(Run::from_line("1\t2\t3\t4\t5\t6\t7\tx\tid"), None);
+        assert_eq!(Run::from_line("1\t2\t3\t4\t5\t6\t7\t8\tbad\\q"), None);
+    }
+}
@@ -0,0 +1,149 @@
+/// Builds a `CreateProcessW` command line that the MSVCRT startup code and
+/// `CommandLineToArgvW` split back into exactly `executable` followed by
+/// `arguments`.
+pub fn build(executable: &str, arguments: &[String]) -> String {
+    let mut command_line = quote_executable(executable);
+    for argument in arguments {
+        command_line.push(' ');
+        quote_argument(argument, &mut command_line);
+    }
+    command_line
+}
+
+/// Quotes `arguments` alone, for APIs such as `ShellExecuteExW` that take the
+/// program and its parameters separately.
+pub fn arguments(arguments: &[String]) -> String {
+    let mut command_line = String::new();
+    for (index, argument) in arguments.iter().enumerate() {
+        if index > 0 {
+            command_line.push(' ');
+        }
+        quote_argument(argument, &mut command_line);
+    }
+    command_line
+}
+
+/// Builds a command line passing `raw` to the program untouched, for programs
+/// that parse their command line with their own rules.
+pub fn build_raw(executable: &str, raw: &str) -> String {
+    let mut command_line = quote_executable(executable);
+    if !raw.is_empty() {
+        command_line.push(' ');
+        command_line.push_str(raw);
+    }
+    command_line
+}
+
+/// The program name is parsed by simpler rules than the arguments: it runs
+/// to the next quote with no backslash escapes. Paths cannot contain quotes,
+/// so wrapping it in a pair is always enough.
+fn quote_executable(executable: &str) -> String {
+    format!("\"{executable}\"")
+}
+
+fn quote_argument(argument: &str, command_line: &mut String) {
+    if !argument.is_empty() && !argument.contains([' ', '\t', '\n', '\x0b', '"']) {
+        command_line.push_str(argument);
+        return;
+    }
+
+    command_line.push('"');
+    let mut backslashes = 0;
+    for character in argument.chars() {
+        match character {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escapes, so each one is
+                // doubled and the quote itself escaped.
+                push_backslashes(command_line, backslashes * 2 + 1);
+                command_line.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                push_backslashes(command_line, backslashes);
+                command_line.push(character);
+                backslashes = 0;
+            }
+        }
+    }
+    // Trailing backslashes precede the closing quote, so they are doubled too.
+    push_backslashes(command_line, backslashes * 2);
+    command_line.push('"');
+}
+
+fn push_backslashes(command_line: &mut String, count: usize) {
+    command_line.extend(std::iter::repeat_n('\\', count));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+    use windows::{
+        Win32::{
+            Foundation::{HLOCAL, LocalFree},
+            UI::Shell::CommandLineToArgvW,
+        },
+        core::PCWSTR,
+    };
+
+    fn split(command_line: &str) -> Vec<String> {
+        let wide: Vec<u16> = command_line.encode_utf16().chain(Some(0)).collect();
+        let mut count = 0;
+        unsafe {
+            let argv = CommandLineToArgvW(PCWSTR(wide.as_ptr()), &mut count);
+            assert!(!argv.is_null());
+            let arguments = std::slice::from_raw_parts(argv, count as usize)
+                .iter()
+                .map(|argument| {
+                    OsString::from_wide(argument.as_wide())
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            LocalFree(Some(HLOCAL(argv as *mut _)));
+            arguments
+        }
+    }
+
+    #[test]
+    fn arguments_round_trip_through_command_line_to_argv() {
+        let executable = "C:\\Program Files\\Game\\game.exe";
+        let arguments: Vec<String> = [
+            "plain",
+            "",
+            "with space",
+            "tab\there",
+            "say \"hi\"",
+            "trailing\\",
+            "trailing space\\",
+            "C:\\path with\\\\",
+            "\\\\server\\share",
+            "a\\\\\"b",
+            "\"",
+            "ünïcødé ✓",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        let command_line = build(executable, &arguments);
+        let parsed = split(&command_line);
+
+        assert_eq!(parsed[0], executable);
+        assert_eq!(parsed[1..], arguments[..]);
+    }
+
+    #[test]
+    fn leaves_simple_arguments_unquoted() {
+        let simple = ["/S".to_string(), "--flag=value".to_string()];
+        assert_eq!(
+            build("C:\\setup.exe", &simple),
+            "\"C:\\setup.exe\" /S --flag=value"
+        );
+        assert_eq!(arguments(&simple), "/S --flag=value");
+        assert_eq!(
+            build_raw("C:\\cmd.exe", "/c echo \"x\""),
+            "\"C:\\cmd.exe\" /c echo \"x\""
+        );
+    }
+}
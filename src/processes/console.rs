@@ -0,0 +1,46 @@
+use std::sync::{Mutex, PoisonError};
+
+use windows::{
+    Win32::System::Console::{
+        AttachConsole, CTRL_BREAK_EVENT, CTRL_C_EVENT, FreeConsole, GenerateConsoleCtrlEvent,
+        SetConsoleCtrlHandler,
+    },
+    core::{BOOL, Error},
+};
+
+/// A process can be attached to at most one console, so attaching to the
+/// console of each target in turn must never interleave.
+static CONSOLE: Mutex<bool> = Mutex::new(false);
+
+/// Swallows the console control events the bridge raises on consoles it has
+/// attached to. A handler is used rather than `SetConsoleCtrlHandler(None,
+/// true)` because that flag would be inherited by every program launched
+/// afterwards.
+unsafe extern "system" fn ignore_control(_event: u32) -> BOOL {
+    true.into()
+}
+
+/// Sends Ctrl+C, or Ctrl+Break when `hard` is set, to every process on the
+/// console of `pid`.
+///
+/// Ctrl+Break cannot be turned off the way Ctrl+C can, but a process that
+/// handles only Ctrl+C is killed by it on the spot, so it is best kept for
+/// processes that ignored Ctrl+C.
+///
+/// Fails when `pid` has no console to attach to, which is the case for GUI
+/// programs.
+pub fn interrupt(pid: u32, hard: bool) -> Result<(), Error> {
+    let mut handler_installed = CONSOLE.lock().unwrap_or_else(PoisonError::into_inner);
+    unsafe {
+        if !*handler_installed {
+            SetConsoleCtrlHandler(Some(ignore_control), true)?;
+            *handler_installed = true;
+        }
+
+        AttachConsole(pid)?;
+        let event = if hard { CTRL_BREAK_EVENT } else { CTRL_C_EVENT };
+        let result = GenerateConsoleCtrlEvent(event, 0);
+        let _ = FreeConsole();
+        result
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    os::windows::io::AsRawHandle,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, mpsc},
+    time::SystemTime,
+};
+
+use super::launch::Launch;
+use super::stack::DBGHELP;
+use super::thread::ThreadHandle;
+use windows::{
+    Win32::{
+        Foundation::{
+            CloseHandle, DBG_CONTINUE, DBG_EXCEPTION_NOT_HANDLED, EXCEPTION_BREAKPOINT, HANDLE,
+            STATUS_WX86_BREAKPOINT,
+        },
+        System::{
+            Diagnostics::Debug::{
+                CREATE_PROCESS_DEBUG_EVENT, ContinueDebugEvent, DEBUG_EVENT, DebugActiveProcess,
+                DebugSetProcessKillOnExit, EXCEPTION_DEBUG_EVENT, EXCEPTION_POINTERS,
+                EXCEPTION_RECORD, EXIT_PROCESS_DEBUG_EVENT, LOAD_DLL_DEBUG_EVENT,
+                MINIDUMP_EXCEPTION_INFORMATION, MiniDumpWithHandleData, MiniDumpWithThreadInfo,
+                MiniDumpWithUnloadedModules, MiniDumpWriteDump, WaitForDebugEvent,
+            },
+            Threading::{INFINITE, THREAD_GET_CONTEXT, THREAD_QUERY_INFORMATION},
+        },
+    },
+    core::{BOOL, Error},
+};
+
+/// An unhandled exception that ended a launched process.
+#[derive(Debug, Clone)]
+pub struct Crash {
+    pub exception_code: u32,
+    /// Where the minidump was written, if writing it succeeded.
+    pub dump_path: Option<PathBuf>,
+}
+
+/// Attaches to the primary process of `launch` as a debugger on a dedicated
+/// thread, which writes a minidump into `directory` when the process raises
+/// an exception it does not handle and records the crash on the launch.
+///
+/// Returns once the debugger is attached, so a process that is still
+/// suspended can be resumed without missing an early crash.
+///
+/// The process runs under a debugger from then on, with what that implies:
+/// `IsDebuggerPresent` returns true, so programs with anti-debugging checks,
+/// DRM among them, may refuse to run or behave differently, and the
+/// process's own unhandled exception filters and crash reporters are
+/// skipped in favour of the minidump.
+pub fn watch(launch: Arc<Launch>, name: String, directory: PathBuf) -> Result<(), Error> {
+    let (attached, result) = mpsc::channel();
+    std::thread::spawn(move || {
+        // Debug events are delivered to the thread that attached, so the
+        // whole session lives on this one.
+        let pid = launch.process.pid();
+        let attach = unsafe { DebugActiveProcess(pid) }
+            .and_then(|()| unsafe { DebugSetProcessKillOnExit(false) });
+        let failed = attach.is_err();
+        let _ = attached.send(attach);
+        if !failed {
+            run(&launch, &name, &directory);
+        }
+    });
+    result.recv().unwrap_or_else(|_| Err(Error::from_thread()))
+}
+
+fn run(launch: &Launch, name: &str, directory: &Path) {
+    let pid = launch.process.pid();
+    // Attaching raises one breakpoint, and a second one in the 32-bit half
+    // of a WOW64 process, which the debugger is expected to swallow.
+    let mut attach_breakpoint = false;
+    let mut wow64_breakpoint = false;
+
+    loop {
+        let mut event = DEBUG_EVENT::default();
+        if let Err(error) = unsafe { WaitForDebugEvent(&mut event, INFINITE) } {
+            tracing::warn!("Failed to wait for debug events of process {pid}: {error}");
+            return;
+        }
+
+        let mut status = DBG_CONTINUE;
+        match event.dwDebugEventCode {
+            CREATE_PROCESS_DEBUG_EVENT => close(unsafe { event.u.CreateProcessInfo.hFile }),
+            LOAD_DLL_DEBUG_EVENT => close(unsafe { event.u.LoadDll.hFile }),
+            EXCEPTION_DEBUG_EVENT => {
+                let exception = unsafe { event.u.Exception };
+                let code = exception.ExceptionRecord.ExceptionCode;
+                if exception.dwFirstChance != 0 {
+                    if code == EXCEPTION_BREAKPOINT && !attach_breakpoint {
+                        attach_breakpoint = true;
+                    } else if code == STATUS_WX86_BREAKPOINT && !wow64_breakpoint {
+                        wow64_breakpoint = true;
+                    } else {
+                        status = DBG_EXCEPTION_NOT_HANDLED;
+                    }
+                } else {
+                    // Second chance: nothing in the process handled it, so
+                    // it dies as soon as the event is continued.
+                    let dump_path = dump_path(directory, name, pid);
+                    let dump_path = match write_dump(
+                        launch,
+                        event.dwThreadId,
+                        exception.ExceptionRecord,
+                        &dump_path,
+                    ) {
+                        Ok(()) => Some(dump_path),
+                        Err(error) => {
+                            tracing::warn!("Failed to write a minidump of process {pid}: {error}");
+                            None
+                        }
+                    };
+                    launch.record_crash(Crash {
+                        exception_code: code.0 as u32,
+                        dump_path,
+                    });
+                    status = DBG_EXCEPTION_NOT_HANDLED;
+                }
+            }
+            _ => {}
+        }
+
+        if let Err(error) =
+            unsafe { ContinueDebugEvent(event.dwProcessId, event.dwThreadId, status) }
+        {
+            tracing::warn!("Failed to continue debug event of process {pid}: {error}");
+            return;
+        }
+        if event.dwDebugEventCode == EXIT_PROCESS_DEBUG_EVENT {
+            return;
+        }
+    }
+}
+
+fn close(handle: HANDLE) {
+    if !handle.is_invalid() {
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+    }
+}
+
+fn dump_path(directory: &Path, name: &str, pid: u32) -> PathBuf {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    directory.join(format!("{name}-{pid}-{timestamp}.dmp"))
+}
+
+fn write_dump(
+    launch: &Launch,
+    tid: u32,
+    mut record: EXCEPTION_RECORD,
+    path: &Path,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(path.parent().unwrap_or(path))?;
+    let file = File::create(path)?;
+
+    // The exception pointers live in our own address space, hence
+    // ClientPointers is false. Without the faulting thread's context the
+    // dump is still written, just without the exception stream.
+    let mut context = ThreadHandle::open(tid, THREAD_GET_CONTEXT | THREAD_QUERY_INFORMATION)
+        .and_then(|thread| thread.context());
+    let mut pointers = context.as_mut().ok().map(|context| EXCEPTION_POINTERS {
+        ExceptionRecord: &mut record,
+        ContextRecord: &mut context.0,
+    });
+    let exception = pointers
+        .as_mut()
+        .map(|pointers| MINIDUMP_EXCEPTION_INFORMATION {
+            ThreadId: tid,
+            ExceptionPointers: pointers,
+            ClientPointers: BOOL::from(false),
+        });
+
+    let _dbghelp = DBGHELP.lock().unwrap_or_else(PoisonError::into_inner);
+    unsafe {
+        MiniDumpWriteDump(
+            launch.process.0.hProcess,
+            launch.process.pid(),
+            HANDLE(file.as_raw_handle()),
+            MiniDumpWithThreadInfo | MiniDumpWithUnloadedModules | MiniDumpWithHandleData,
+            exception
+                .as_ref()
+                .map(|exception| exception as *const MINIDUMP_EXCEPTION_INFORMATION),
+            None,
+            None,
+        )
+    }
+}
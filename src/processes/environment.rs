@@ -0,0 +1,78 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStrExt,
+};
+
+/// Builds a `CREATE_UNICODE_ENVIRONMENT` block from `base`, with the names in
+/// `unset` removed and then the variables in `set` added or replaced.
+///
+/// Variable names compare case-insensitively, as Windows does, and the block
+/// is sorted by upper-cased name, as `CreateProcessW` expects.
+pub fn block(
+    base: impl IntoIterator<Item = (OsString, OsString)>,
+    set: &HashMap<String, String>,
+    unset: &[String],
+) -> Vec<u16> {
+    let mut variables: BTreeMap<OsString, (OsString, OsString)> = base
+        .into_iter()
+        .map(|(name, value)| (key(&name), (name, value)))
+        .collect();
+    for name in unset {
+        variables.remove(&key(OsStr::new(name)));
+    }
+    for (name, value) in set {
+        variables.insert(key(OsStr::new(name)), (name.into(), value.into()));
+    }
+
+    let mut block = Vec::new();
+    for (name, value) in variables.values() {
+        block.extend(name.encode_wide());
+        block.push(u16::from(b'='));
+        block.extend(value.encode_wide());
+        block.push(0);
+    }
+    // An empty block still needs its own terminator after the (absent) last
+    // variable's one.
+    if block.is_empty() {
+        block.push(0);
+    }
+    block.push(0);
+    block
+}
+
+fn key(name: &OsStr) -> OsString {
+    name.to_string_lossy().to_uppercase().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(block: &[u16]) -> Vec<String> {
+        block
+            .split(|&unit| unit == 0)
+            .take_while(|entry| !entry.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect()
+    }
+
+    #[test]
+    fn builds_sorted_case_insensitive_blocks() {
+        let base = [("Path", "C:\\windows"), ("WINEDEBUG", "-all"), ("b", "1")]
+            .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+        let set = HashMap::from([
+            ("winedebug".to_string(), "+relay".to_string()),
+            ("DXVK_HUD".to_string(), "fps".to_string()),
+        ]);
+        let block = block(base, &set, &["PATH".to_string()]);
+
+        assert_eq!(decode(&block), ["b=1", "DXVK_HUD=fps", "winedebug=+relay"]);
+        assert_eq!(&block[block.len() - 2..], [0, 0]);
+    }
+
+    #[test]
+    fn terminates_empty_blocks_twice() {
+        assert_eq!(block([], &HashMap::new(), &[]), [0, 0]);
+    }
+}
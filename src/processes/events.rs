@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use super::job::Job;
+use super::launch_table::{LaunchTable, Member};
+use super::process::ProcessHandle;
+use next_proto::winebridge;
+use tokio::sync::broadcast;
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, ERROR_INVALID_PARAMETER, HANDLE, INVALID_HANDLE_VALUE},
+        System::{
+            IO::{CreateIoCompletionPort, GetQueuedCompletionStatus, OVERLAPPED},
+            SystemServices::{
+                JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS, JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO,
+                JOB_OBJECT_MSG_EXIT_PROCESS, JOB_OBJECT_MSG_NEW_PROCESS,
+            },
+            Threading::{INFINITE, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE},
+        },
+    },
+    core::{Error, HRESULT},
+};
+
+/// Events buffered for each subscriber before the slowest one starts
+/// missing some.
+const EVENT_BUFFER: usize = 256;
+
+/// How long an exit notification waits for the process to be signalled, as
+/// the notification can arrive just before the exit code is final.
+const EXIT_WAIT: Duration = Duration::from_secs(1);
+
+/// An I/O completion port, closed on drop.
+struct CompletionPort(HANDLE);
+
+// SAFETY: completion ports are plain kernel handles, made to be waited on
+// and posted to from any thread.
+unsafe impl Send for CompletionPort {}
+unsafe impl Sync for CompletionPort {}
+
+impl Drop for CompletionPort {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Turns the notifications of every launch's job into process events and
+/// broadcasts them to all subscribers.
+///
+/// Each job is associated with one completion port under a key derived from
+/// its launch id, and a single thread reads the port for as long as the
+/// bridge runs.
+pub struct EventHub {
+    port: CompletionPort,
+    /// Launch id of the jobs associated under each completion key.
+    ///
+    /// Entries are never removed: a job's notifications can arrive well
+    /// after its id has been launched again, so removing the entry once a
+    /// job empties could silence the relaunch. The key is derived from the
+    /// id, so this grows only with the number of distinct ids.
+    jobs: Mutex<HashMap<usize, String>>,
+    /// Learns of every process joining a launch, so a later bridge instance
+    /// can recover it along with the rest of the launch.
+    table: Arc<LaunchTable>,
+    sender: broadcast::Sender<winebridge::ProcessEvent>,
+}
+
+impl EventHub {
+    pub fn new(table: Arc<LaunchTable>) -> Result<Arc<Self>, Error> {
+        let port =
+            CompletionPort(unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, None, 0, 1) }?);
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        let hub = Arc::new(Self {
+            port,
+            jobs: Mutex::default(),
+            table,
+            sender,
+        });
+
+        let reader = hub.clone();
+        std::thread::spawn(move || reader.run());
+        Ok(hub)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<winebridge::ProcessEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Reports the processes of `job`, launched as `id`, from now on.
+    ///
+    /// A job joined by a later launch of the same id is already associated,
+    /// which fails with `ERROR_INVALID_PARAMETER`, but under the same key,
+    /// which still maps to its id.
+    pub fn watch(&self, job: &Job, id: &str) -> Result<(), Error> {
+        let key = key(id);
+        self.jobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, id.to_string());
+        match job.associate_completion_port(self.port.0, key) {
+            Err(error) if error.code() == HRESULT::from_win32(ERROR_INVALID_PARAMETER.0) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn run(&self) {
+        // Processes are opened as they join their job, so their exit code
+        // can still be read, and their pid is not reused, when they exit.
+        let mut processes = HashMap::new();
+        loop {
+            let mut message = 0;
+            let mut key = 0;
+            let mut overlapped: *mut OVERLAPPED = std::ptr::null_mut();
+            if let Err(error) = unsafe {
+                GetQueuedCompletionStatus(
+                    self.port.0,
+                    &mut message,
+                    &mut key,
+                    &mut overlapped,
+                    INFINITE,
+                )
+            } {
+                tracing::warn!("Failed to read job notifications: {error}");
+                return;
+            }
+            // Job notifications carry the pid where an OVERLAPPED would be.
+            let pid = overlapped as usize as u32;
+
+            let Some(launch_id) = self
+                .jobs
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&key)
+                .cloned()
+            else {
+                continue;
+            };
+            let event = |kind: winebridge::ProcessEventKind| winebridge::ProcessEvent {
+                launch_id: launch_id.clone(),
+                kind: kind as i32,
+                ..Default::default()
+            };
+            let event = match message {
+                JOB_OBJECT_MSG_NEW_PROCESS => {
+                    match ProcessHandle::open(
+                        pid,
+                        PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SYNCHRONIZE,
+                    ) {
+                        Ok(process) => {
+                            self.remember(&launch_id, pid, &process);
+                            processes.insert(pid, process);
+                        }
+                        Err(error) => tracing::debug!("Failed to open process {pid}: {error}"),
+                    }
+                    winebridge::ProcessEvent {
+                        pid,
+                        ..event(winebridge::ProcessEventKind::Started)
+                    }
+                }
+                JOB_OBJECT_MSG_EXIT_PROCESS | JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS => {
+                    winebridge::ProcessEvent {
+                        pid,
+                        exit_code: processes
+                            .remove(&pid)
+                            .and_then(|process| process.wait(Some(EXIT_WAIT)).ok()),
+                        abnormal: message == JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS,
+                        ..event(winebridge::ProcessEventKind::Exited)
+                    }
+                }
+                JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO => event(winebridge::ProcessEventKind::JobEmpty),
+                _ => continue,
+            };
+            // Sending only fails while nobody is subscribed.
+            let _ = self.sender.send(event);
+        }
+    }
+
+    /// Adds process `pid`, which just joined launch `id`, to the launch's
+    /// record in the launch table. Launches that are not in the table are
+    /// left alone by it.
+    fn remember(&self, id: &str, pid: u32, process: &ProcessHandle) {
+        let added = process
+            .created()
+            .map_err(std::io::Error::from)
+            .and_then(|created| self.table.add_member(id, Member { pid, created }));
+        if let Err(error) = added {
+            tracing::warn!("Failed to record process {pid} of launch {id}: {error}");
+        }
+    }
+}
+
+/// Completion key of the jobs of launch `id`. The same id always maps to the
+/// same key, which keeps every run of the id reporting under it.
+fn key(id: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    hasher.finish() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::processes::testing;
+    use next_proto::winebridge::{self, ProcessEventKind};
+
+    #[tokio::test]
+    async fn reports_the_lifecycle_of_a_launch_in_order() {
+        let processes = testing::manager("events");
+        let mut events = processes.events().unwrap().subscribe();
+        let request = winebridge::LaunchProcessRequest {
+            id: testing::unique_name("events"),
+            executable: testing::system_program("cmd"),
+            command_line: Some("/c exit 5".to_string()),
+            lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+            ..Default::default()
+        };
+        let reservation = processes.reserve(&request).unwrap();
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+        let pid = launch.process.pid();
+
+        let mut received = Vec::new();
+        let collected = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            loop {
+                let event = events.recv().await.unwrap();
+                if event.launch_id != request.id {
+                    continue;
+                }
+                let kind = event.kind();
+                received.push(event);
+                if kind == ProcessEventKind::JobEmpty {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(collected.is_ok(), "the job never emptied: {received:?}");
+
+        let summary: Vec<_> = received
+            .iter()
+            .map(|event| (event.kind(), event.pid, event.exit_code))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ProcessEventKind::Started, pid, None),
+                (ProcessEventKind::Exited, pid, Some(5)),
+                (ProcessEventKind::JobEmpty, 0, None),
+            ]
+        );
+    }
+}
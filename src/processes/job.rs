@@ -0,0 +1,324 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use super::to_wide_string;
+use next_proto::winebridge;
+use windows::{
+    Win32::{
+        Foundation::{
+            CloseHandle, ERROR_ALREADY_EXISTS, ERROR_MORE_DATA, GetLastError, HANDLE, SetLastError,
+            WIN32_ERROR,
+        },
+        System::JobObjects::{
+            AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_CPU_RATE_CONTROL_ENABLE,
+            JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP, JOB_OBJECT_LIMIT_ACTIVE_PROCESS,
+            JOB_OBJECT_LIMIT_AFFINITY, JOB_OBJECT_LIMIT_JOB_MEMORY,
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOB_OBJECT_LIMIT_PRIORITY_CLASS,
+            JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOBOBJECT_ASSOCIATE_COMPLETION_PORT,
+            JOBOBJECT_BASIC_ACCOUNTING_INFORMATION, JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION,
+            JOBOBJECT_BASIC_PROCESS_ID_LIST, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+            JOBOBJECT_CPU_RATE_CONTROL_INFORMATION_0, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+            JOBOBJECTINFOCLASS, JobObjectAssociateCompletionPortInformation,
+            JobObjectBasicAccountingInformation, JobObjectBasicAndIoAccountingInformation,
+            JobObjectBasicProcessIdList, JobObjectCpuRateControlInformation,
+            JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+            TerminateJobObject,
+        },
+        System::Threading::PROCESS_CREATION_FLAGS,
+    },
+    core::{Error, HRESULT, PCWSTR},
+};
+
+/// How often waits re-check a job for processes that have not exited yet.
+pub const JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Numbers the runs this bridge instance creates jobs for, to name them.
+static NEXT_RUN: AtomicU64 = AtomicU64::new(0);
+
+/// Number of pids the first `JobObjectBasicProcessIdList` query makes room for.
+const INITIAL_PROCESS_ID_CAPACITY: usize = 64;
+
+/// Resources used by every process that ever ran in a job, exited ones
+/// included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobAccounting {
+    pub user_time: Duration,
+    pub kernel_time: Duration,
+    /// Most memory committed by all processes of the job at once.
+    pub peak_memory: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    /// Bytes transferred by I/O other than reads and writes, such as
+    /// device control.
+    pub other_bytes: u64,
+}
+
+fn ticks_duration(ticks: i64) -> Duration {
+    Duration::from_nanos((ticks.max(0) as u64).saturating_mul(100))
+}
+
+/// The job object grouping every process of one run of a launch id. Each
+/// run gets a fresh job, named after the id so a later bridge instance can
+/// open it again; launches joining a run open its job by that name.
+pub struct Job {
+    handle: HANDLE,
+    name: Option<String>,
+}
+
+// SAFETY: job object handles are plain kernel handles and every operation on
+// them is safe to issue from any thread.
+unsafe impl Send for Job {}
+unsafe impl Sync for Job {}
+
+impl Job {
+    /// Opens the job called `name`, creating it when it does not exist.
+    pub fn open(name: &str) -> Result<Self, Error> {
+        let wide = to_wide_string(name);
+        Ok(Self {
+            handle: unsafe { CreateJobObjectW(None, PCWSTR(wide.as_ptr()))? },
+            name: Some(name.to_string()),
+        })
+    }
+
+    /// Creates a job for a new run of launch `id`, under a name no existing
+    /// job has, so nothing an earlier run left in its job (limits, counters,
+    /// peak memory) carries over.
+    pub fn create(id: &str) -> Result<Self, Error> {
+        loop {
+            let run = NEXT_RUN.fetch_add(1, Ordering::Relaxed);
+            let name = format!("{id}#{}-{run}", std::process::id());
+            let wide = to_wide_string(&name);
+            let handle = unsafe {
+                SetLastError(WIN32_ERROR(0));
+                CreateJobObjectW(None, PCWSTR(wide.as_ptr()))?
+            };
+            let existed = unsafe { GetLastError() } == ERROR_ALREADY_EXISTS;
+            let job = Self {
+                handle,
+                name: Some(name),
+            };
+            // Left behind by an earlier bridge instance with the same pid.
+            if !existed {
+                return Ok(job);
+            }
+        }
+    }
+
+    /// A job no name refers to, which nothing else can open or join.
+    pub fn anonymous() -> Result<Self, Error> {
+        Ok(Self {
+            handle: unsafe { CreateJobObjectW(None, PCWSTR::null())? },
+            name: None,
+        })
+    }
+
+    /// Name to open the job by, or `None` for an anonymous job.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn assign(&self, process: HANDLE) -> Result<(), Error> {
+        unsafe { AssignProcessToJobObject(self.handle, process) }
+    }
+
+    pub fn terminate(&self, exit_code: u32) -> Result<(), Error> {
+        unsafe { TerminateJobObject(self.handle, exit_code) }
+    }
+
+    /// Applies the requested limits on top of whatever limits the job already
+    /// has, such as those of the run a launch joins, so processes started
+    /// into it later are held to them as well. Killing on close is left to
+    /// [`Self::set_kill_on_close`].
+    pub fn set_limits(&self, limits: &winebridge::ProcessLimits) -> Result<(), Error> {
+        self.update_limits(|info| {
+            let basic = &mut info.BasicLimitInformation;
+            if let Some(bytes) = limits.job_memory_bytes {
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+                info.JobMemoryLimit = bytes as usize;
+            }
+            if let Some(bytes) = limits.process_memory_bytes {
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = bytes as usize;
+            }
+            if let Some(count) = limits.active_processes {
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+                basic.ActiveProcessLimit = count;
+            }
+        })?;
+
+        if let Some(percent) = limits.cpu_rate_percent {
+            // CpuRate is expressed in hundredths of a percent of all CPUs.
+            let rate = JOBOBJECT_CPU_RATE_CONTROL_INFORMATION {
+                ControlFlags: JOB_OBJECT_CPU_RATE_CONTROL_ENABLE
+                    | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+                Anonymous: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION_0 {
+                    CpuRate: percent * 100,
+                },
+            };
+            self.set(JobObjectCpuRateControlInformation, &rate)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs every process in the job, and every process started into it
+    /// later, at priority `class`.
+    pub fn set_priority_class(&self, class: PROCESS_CREATION_FLAGS) -> Result<(), Error> {
+        self.update_limits(|info| {
+            let basic = &mut info.BasicLimitInformation;
+            basic.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+            basic.PriorityClass = class.0;
+        })
+    }
+
+    /// Whether every process in the job is terminated once its last handle
+    /// is closed, which at the latest happens when the bridge exits. Unlike
+    /// the other limits this one is cleared again when `kill` is false, so a
+    /// joined job follows the policy of its latest launch.
+    pub fn set_kill_on_close(&self, kill: bool) -> Result<(), Error> {
+        self.update_limits(|info| {
+            let flags = &mut info.BasicLimitInformation.LimitFlags;
+            if kill {
+                *flags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            } else {
+                *flags &= !JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            }
+        })
+    }
+
+    /// Restricts every process in the job, and every process started into it
+    /// later, to the CPUs set in `mask`.
+    pub fn set_affinity(&self, mask: u64) -> Result<(), Error> {
+        self.update_limits(|info| {
+            let basic = &mut info.BasicLimitInformation;
+            basic.LimitFlags |= JOB_OBJECT_LIMIT_AFFINITY;
+            basic.Affinity = mask as usize;
+        })
+    }
+
+    /// Has the job post its notifications to `port` under `key`. A job can
+    /// only ever be associated with one port.
+    pub fn associate_completion_port(&self, port: HANDLE, key: usize) -> Result<(), Error> {
+        let info = JOBOBJECT_ASSOCIATE_COMPLETION_PORT {
+            CompletionKey: key as *mut _,
+            CompletionPort: port,
+        };
+        self.set(JobObjectAssociateCompletionPortInformation, &info)
+    }
+
+    /// Number of processes in the job that have not exited yet.
+    pub fn active_processes(&self) -> Result<u32, Error> {
+        let info: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION =
+            self.query(JobObjectBasicAccountingInformation)?;
+        Ok(info.ActiveProcesses)
+    }
+
+    /// CPU time used by every process that ever ran in the job, exited ones
+    /// included.
+    pub fn cpu_time(&self) -> Result<Duration, Error> {
+        let info: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION =
+            self.query(JobObjectBasicAccountingInformation)?;
+        Ok(ticks_duration(info.TotalUserTime + info.TotalKernelTime))
+    }
+
+    pub fn accounting(&self) -> Result<JobAccounting, Error> {
+        let info: JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION =
+            self.query(JobObjectBasicAndIoAccountingInformation)?;
+        let limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION =
+            self.query(JobObjectExtendedLimitInformation)?;
+        Ok(JobAccounting {
+            user_time: ticks_duration(info.BasicInfo.TotalUserTime),
+            kernel_time: ticks_duration(info.BasicInfo.TotalKernelTime),
+            peak_memory: limits.PeakJobMemoryUsed as u64,
+            read_bytes: info.IoInfo.ReadTransferCount,
+            write_bytes: info.IoInfo.WriteTransferCount,
+            other_bytes: info.IoInfo.OtherTransferCount,
+        })
+    }
+
+    /// Pids of the processes currently in the job.
+    pub fn process_ids(&self) -> Result<Vec<u32>, Error> {
+        // The list is a fixed header followed by a variable number of
+        // ULONG_PTR pids, so it is built in a usize buffer to keep the
+        // header and every entry aligned.
+        let header = std::mem::offset_of!(JOBOBJECT_BASIC_PROCESS_ID_LIST, ProcessIdList)
+            / std::mem::size_of::<usize>();
+        let mut capacity = INITIAL_PROCESS_ID_CAPACITY;
+        loop {
+            let mut buffer = vec![0usize; header + capacity];
+            let result = unsafe {
+                QueryInformationJobObject(
+                    Some(self.handle),
+                    JobObjectBasicProcessIdList,
+                    buffer.as_mut_ptr() as *mut _,
+                    std::mem::size_of_val(buffer.as_slice()) as u32,
+                    None,
+                )
+            };
+            if let Err(error) = result
+                && error.code() != HRESULT::from_win32(ERROR_MORE_DATA.0)
+            {
+                return Err(error);
+            }
+
+            let list = unsafe { &*(buffer.as_ptr() as *const JOBOBJECT_BASIC_PROCESS_ID_LIST) };
+            let assigned = list.NumberOfAssignedProcesses as usize;
+            if assigned > capacity {
+                capacity = assigned;
+                continue;
+            }
+
+            let listed = list.NumberOfProcessIdsInList as usize;
+            return Ok(buffer[header..header + listed]
+                .iter()
+                .map(|&pid| pid as u32)
+                .collect());
+        }
+    }
+
+    /// Reads the job's current limits, lets `update` change them and writes
+    /// them back, so limits set earlier are kept.
+    fn update_limits(
+        &self,
+        update: impl FnOnce(&mut JOBOBJECT_EXTENDED_LIMIT_INFORMATION),
+    ) -> Result<(), Error> {
+        let mut info = self.query(JobObjectExtendedLimitInformation)?;
+        update(&mut info);
+        self.set(JobObjectExtendedLimitInformation, &info)
+    }
+
+    fn set<T>(&self, class: JOBOBJECTINFOCLASS, info: &T) -> Result<(), Error> {
+        unsafe {
+            SetInformationJobObject(
+                self.handle,
+                class,
+                info as *const T as *const _,
+                std::mem::size_of::<T>() as u32,
+            )
+        }
+    }
+
+    fn query<T: Default>(&self, class: JOBOBJECTINFOCLASS) -> Result<T, Error> {
+        let mut info = T::default();
+        unsafe {
+            QueryInformationJobObject(
+                Some(self.handle),
+                class,
+                &mut info as *mut T as *mut _,
+                std::mem::size_of::<T>() as u32,
+                None,
+            )
+        }?;
+        Ok(info)
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::crash::Crash;
+use super::job::{JOB_POLL_INTERVAL, Job};
+use super::launch_table::{LaunchRecord, Member};
+use super::pipe::{PipeHandle, PipeWriter};
+use super::process::ProcessInfo;
+use super::thread::{ThreadHandle, ThreadSnapshot};
+use super::watchdog::Watchdog;
+use windows::{
+    Win32::{Foundation::ERROR_TIMEOUT, System::Threading::THREAD_SUSPEND_RESUME},
+    core::{Error, HRESULT},
+};
+
+pub struct ExitStatus {
+    pub exit_code: u32,
+    pub duration: Duration,
+    /// The unhandled exception the primary process died of, for launches
+    /// watched for crashes.
+    pub crash: Option<Crash>,
+    /// The watchdog that terminated the launch, if one did.
+    pub watchdog: Option<Watchdog>,
+}
+
+/// What a launch ran, as reported back to clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub executable: String,
+    pub command_line: String,
+}
+
+/// A program started through [`super::manager::ProcessManager`], kept alive
+/// for as long as the bridge may still be asked about it.
+pub struct Launch {
+    pub job: Job,
+    pub process: ProcessInfo,
+    pub command: Command,
+    pub started: Instant,
+    /// Wall-clock time of [`Self::started`], for reporting.
+    pub started_at: SystemTime,
+    /// When [`Self::wait`] first found the job empty, which ends the run.
+    ended: OnceLock<Instant>,
+    /// Our end of the pipe replacing the primary process's stdin, if the
+    /// launch asked for one and it has not been closed yet.
+    stdin: PipeWriter,
+    /// Threads [`Self::suspend`] stopped, by tid, kept open so
+    /// [`Self::resume`] restarts exactly those even if their tids get reused.
+    suspended: Mutex<HashMap<u32, ThreadHandle>>,
+    crash: Mutex<Option<Crash>>,
+    watchdog: Mutex<Option<Watchdog>>,
+}
+
+impl Launch {
+    pub fn new(
+        job: Job,
+        process: ProcessInfo,
+        command: Command,
+        stdin: Option<PipeHandle>,
+    ) -> Self {
+        Self {
+            job,
+            process,
+            command,
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            ended: OnceLock::new(),
+            stdin: PipeWriter::new(stdin),
+            suspended: Mutex::default(),
+            crash: Mutex::default(),
+            watchdog: Mutex::default(),
+        }
+    }
+
+    /// What the launch table keeps of this launch, with the processes in
+    /// its job right now as members.
+    pub fn record(&self) -> Result<LaunchRecord, Error> {
+        let members = self
+            .job
+            .process_ids()?
+            .into_iter()
+            .filter_map(|pid| Member::of(pid).ok())
+            .collect();
+        Ok(LaunchRecord {
+            command: self.command.clone(),
+            primary_pid: self.process.pid(),
+            job: self.job.name().unwrap_or_default().to_string(),
+            members,
+            started_at: self.started_at,
+        })
+    }
+
+    /// Writes `data` to the primary process's stdin, failing with
+    /// `ERROR_BROKEN_PIPE` when stdin was not redirected or is already closed.
+    pub fn write_stdin(&self, data: &[u8]) -> Result<(), Error> {
+        self.stdin.write_all(data)
+    }
+
+    /// Closes the stdin pipe, so the process reads end-of-file once it has
+    /// consumed everything written so far. A write still waiting for the
+    /// process to read is cancelled.
+    pub fn close_stdin(&self) {
+        self.stdin.close();
+    }
+
+    /// Blocks until the primary process and every other process in the job
+    /// have exited, failing with `ERROR_TIMEOUT` once `timeout` elapses.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<ExitStatus, Error> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let remaining =
+            || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        let exit_code = self.process.wait(remaining())?;
+        while self.job.active_processes()? > 0 {
+            if remaining().is_some_and(|remaining| remaining.is_zero()) {
+                return Err(Error::from_hresult(HRESULT::from_win32(ERROR_TIMEOUT.0)));
+            }
+            std::thread::sleep(JOB_POLL_INTERVAL);
+        }
+        self.ended.get_or_init(Instant::now);
+
+        Ok(ExitStatus {
+            exit_code,
+            duration: self.run_time(),
+            crash: self.crash(),
+            watchdog: *self.watchdog.lock().unwrap_or_else(PoisonError::into_inner),
+        })
+    }
+
+    /// How long the launch ran until its job emptied, or has run so far
+    /// while it has not been seen empty yet.
+    pub fn run_time(&self) -> Duration {
+        self.ended
+            .get()
+            .map_or_else(|| self.started.elapsed(), |ended| *ended - self.started)
+    }
+
+    pub fn crash(&self) -> Option<Crash> {
+        self.crash
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn record_crash(&self, crash: Crash) {
+        *self.crash.lock().unwrap_or_else(PoisonError::into_inner) = Some(crash);
+    }
+
+    /// Suspends every thread of every process in the job.
+    ///
+    /// Threads are suspended at most once, so suspending an already suspended
+    /// launch only stops the threads started since. Passes repeat until one
+    /// finds nothing left to suspend, catching threads started meanwhile.
+    pub fn suspend(&self) -> Result<(), Error> {
+        let mut suspended = self
+            .suspended
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let pids = self.job.process_ids()?;
+            let mut progressed = false;
+            for thread in ThreadSnapshot::new()? {
+                if !pids.contains(&thread.owner_pid()) || suspended.contains_key(&thread.tid()) {
+                    continue;
+                }
+                // Threads may exit between the snapshot and opening them.
+                let handle = match ThreadHandle::open(thread.tid(), THREAD_SUSPEND_RESUME) {
+                    Ok(handle) => handle,
+                    Err(error) => {
+                        tracing::debug!("Failed to open thread {}: {error}", thread.tid());
+                        continue;
+                    }
+                };
+                match handle.suspend() {
+                    Ok(()) => {
+                        suspended.insert(thread.tid(), handle);
+                        progressed = true;
+                    }
+                    Err(error) => {
+                        tracing::debug!("Failed to suspend thread {}: {error}", thread.tid());
+                    }
+                }
+            }
+            if !progressed {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Whether [`Self::suspend`] stopped threads that have not been resumed.
+    pub fn is_suspended(&self) -> bool {
+        !self
+            .suspended
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    /// Resumes the threads [`Self::suspend`] stopped.
+    pub fn resume(&self) -> Result<(), Error> {
+        let mut suspended = self
+            .suspended
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (tid, handle) in suspended.drain() {
+            if let Err(error) = handle.resume() {
+                tracing::debug!("Failed to resume thread {tid}: {error}");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_watchdog(&self, watchdog: Watchdog) {
+        *self.watchdog.lock().unwrap_or_else(PoisonError::into_inner) = Some(watchdog);
+    }
+}
@@ -0,0 +1,374 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use super::job::Job;
+use super::launch::Command;
+use super::process::ProcessHandle;
+use super::state::{self, escape, unescape};
+use windows::{
+    Win32::{
+        Foundation::ERROR_NOT_FOUND,
+        System::Threading::{
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_QUOTA, PROCESS_SYNCHRONIZE,
+            PROCESS_TERMINATE,
+        },
+    },
+    core::{Error, HRESULT},
+};
+
+/// Launch table file, inside [`state::directory`].
+pub const TABLE_FILE: &str = "launches.tsv";
+
+/// A process of a launch, told apart from a later process reusing its pid
+/// by its creation time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Member {
+    pub pid: u32,
+    pub created: SystemTime,
+}
+
+impl Member {
+    pub fn of(pid: u32) -> Result<Self, Error> {
+        let created = ProcessHandle::open(pid, PROCESS_QUERY_LIMITED_INFORMATION)?.created()?;
+        Ok(Self { pid, created })
+    }
+
+    /// Opens the process again with the access adopting it takes, failing
+    /// with `ERROR_NOT_FOUND` once it has exited or its pid is reused.
+    fn open(&self) -> Result<ProcessHandle, Error> {
+        let process = ProcessHandle::open(
+            self.pid,
+            PROCESS_QUERY_LIMITED_INFORMATION
+                | PROCESS_SET_QUOTA
+                | PROCESS_TERMINATE
+                | PROCESS_SYNCHRONIZE,
+        )?;
+        // An exited process can still be opened while anything holds a
+        // handle to it.
+        if process.created()? != self.created || process.wait(Some(Duration::ZERO)).is_ok() {
+            return Err(Error::from_hresult(HRESULT::from_win32(ERROR_NOT_FOUND.0)));
+        }
+        Ok(process)
+    }
+}
+
+/// What the bridge remembers of a launch across restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchRecord {
+    pub command: Command,
+    pub primary_pid: u32,
+    /// Name of the job of the run, see [`Job::create`].
+    pub job: String,
+    /// Every process seen in the job, the primary one included, as the
+    /// launch was registered and as each one joined since.
+    pub members: Vec<Member>,
+    pub started_at: SystemTime,
+}
+
+/// A launch made by a previous bridge instance that still had processes
+/// running when this one started.
+pub struct Recovered {
+    pub job: Job,
+    pub record: LaunchRecord,
+}
+
+impl Recovered {
+    /// Finds the processes of launch `id` again, failing with
+    /// `ERROR_NOT_FOUND` when all of them have exited.
+    ///
+    /// Its job is opened by name, which finds it with every process still in
+    /// it when it outlived the previous bridge. Recorded members still
+    /// running are put into it either way, in case the job went away with
+    /// the previous bridge's handles and this opened a new one.
+    pub fn adopt(id: &str, record: LaunchRecord) -> Result<Self, Error> {
+        let job = Job::open(&record.job)?;
+        for member in &record.members {
+            let adopted = member.open().and_then(|process| job.assign(process.raw()));
+            if let Err(error) = adopted {
+                tracing::debug!(
+                    "Did not adopt process {} of launch {id}: {error}",
+                    member.pid
+                );
+            }
+        }
+        if job.active_processes()? == 0 {
+            return Err(Error::from_hresult(HRESULT::from_win32(ERROR_NOT_FOUND.0)));
+        }
+        Ok(Self { job, record })
+    }
+}
+
+fn unix_time(time: SystemTime) -> Duration {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// One line per launch: tab-separated fields, with the members as
+/// comma-separated `pid:creation` pairs, creation times in nanoseconds.
+fn to_line(id: &str, record: &LaunchRecord) -> String {
+    let members = record
+        .members
+        .iter()
+        .map(|member| format!("{}:{}", member.pid, unix_time(member.created).as_nanos()))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        unix_time(record.started_at).as_millis(),
+        record.primary_pid,
+        escape(&record.job),
+        members,
+        escape(&record.command.executable),
+        escape(&record.command.command_line),
+        escape(id),
+    )
+}
+
+fn from_line(line: &str) -> Option<(String, LaunchRecord)> {
+    let mut fields = line.splitn(7, '\t');
+    let started = fields.next()?.parse().ok()?;
+    let primary_pid = fields.next()?.parse().ok()?;
+    let job = unescape(fields.next()?)?;
+    let members = fields
+        .next()?
+        .split(',')
+        .filter(|member| !member.is_empty())
+        .map(|member| {
+            let (pid, created) = member.split_once(':')?;
+            Some(Member {
+                pid: pid.parse().ok()?,
+                created: SystemTime::UNIX_EPOCH + Duration::from_nanos(created.parse().ok()?),
+            })
+        })
+        .collect::<Option<_>>()?;
+    let executable = unescape(fields.next()?)?;
+    let command_line = unescape(fields.next()?)?;
+    let id = unescape(fields.next()?)?;
+
+    Some((
+        id,
+        LaunchRecord {
+            command: Command {
+                executable,
+                command_line,
+            },
+            primary_pid,
+            job,
+            members,
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_millis(started),
+        },
+    ))
+}
+
+/// Launches that outlive the bridge, by id, mirrored to a file inside the
+/// prefix so the next bridge instance can find their processes again.
+pub struct LaunchTable {
+    path: PathBuf,
+    records: Mutex<HashMap<String, LaunchRecord>>,
+}
+
+impl Default for LaunchTable {
+    fn default() -> Self {
+        Self::new(state::directory().join(TABLE_FILE))
+    }
+}
+
+impl LaunchTable {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            records: Mutex::default(),
+        }
+    }
+
+    /// Every launch the file records, as a previous bridge left it. Lines
+    /// that cannot be parsed are skipped.
+    pub fn load(&self) -> io::Result<HashMap<String, LaunchRecord>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents.lines().filter_map(from_line).collect()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Adds or replaces the launch `id` and writes the table out.
+    pub fn insert(&self, id: String, record: LaunchRecord) -> io::Result<()> {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        records.insert(id, record);
+        self.save(&records)
+    }
+
+    /// Drops the launch `id`, if it is in the table, and writes the table out.
+    pub fn remove(&self, id: &str) -> io::Result<()> {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        if records.remove(id).is_none() {
+            return Ok(());
+        }
+        self.save(&records)
+    }
+
+    /// Drops the launch `id` if the table still holds the record of the run
+    /// in `job` for it, rather than that of a later launch of the same id,
+    /// and writes the table out.
+    pub fn remove_record(&self, id: &str, job: &str) -> io::Result<()> {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        if records.get(id).is_none_or(|record| record.job != job) {
+            return Ok(());
+        }
+        records.remove(id);
+        self.save(&records)
+    }
+
+    /// Adds `member` to the launch `id`, if the table holds it, forgetting
+    /// members that have exited since, and writes the table out.
+    pub fn add_member(&self, id: &str, member: Member) -> io::Result<()> {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(record) = records.get_mut(id) else {
+            return Ok(());
+        };
+        if record.members.contains(&member) {
+            return Ok(());
+        }
+        record.members.retain(|member| member.open().is_ok());
+        record.members.push(member);
+        self.save(&records)
+    }
+
+    /// Replaces the whole table, dropping launches that are gone.
+    pub fn replace(&self, replacement: HashMap<String, LaunchRecord>) -> io::Result<()> {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        *records = replacement;
+        self.save(&records)
+    }
+
+    /// Writes the table to a temporary file first and renames it into
+    /// place, so a crash never leaves a half-written table behind.
+    fn save(&self, records: &HashMap<String, LaunchRecord>) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents: String = records
+            .iter()
+            .map(|(id, record)| to_line(id, record))
+            .collect();
+        let pending = self.path.with_extension("tmp");
+        fs::write(&pending, contents)?;
+        fs::rename(&pending, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processes::testing;
+
+    #[test]
+    fn round_trips_table_lines() {
+        let member = |pid, nanos| Member {
+            pid,
+            created: SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos),
+        };
+        let record = LaunchRecord {
+            command: Command {
+                executable: r"C:\Games\game.exe".to_string(),
+                command_line: "\"C:\\Games\\game.exe\" -windowed\t-x".to_string(),
+            },
+            primary_pid: 1234,
+            job: "game#42-0".to_string(),
+            members: vec![
+                member(1234, 1_700_000_000_122_999_900),
+                member(5678, 1_700_000_000_500_000_000),
+            ],
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+        };
+        let line = to_line("game", &record);
+
+        assert_eq!(
+            from_line(line.trim_end_matches('\n')),
+            Some(("game".to_string(), record.clone()))
+        );
+
+        let no_members = LaunchRecord {
+            members: Vec::new(),
+            ..record
+        };
+        let line = to_line("game", &no_members);
+        assert_eq!(
+            from_line(line.trim_end_matches('\n')),
+            Some(("game".to_string(), no_members))
+        );
+    }
+
+    #[test]
+    fn removes_only_the_record_it_is_given() {
+        let path = std::env::temp_dir()
+            .join(testing::unique_name("launch-table"))
+            .join(TABLE_FILE);
+        let table = LaunchTable::new(path.clone());
+        let record = LaunchRecord {
+            command: Command {
+                executable: "game.exe".to_string(),
+                command_line: "game.exe".to_string(),
+            },
+            primary_pid: 1234,
+            job: "game#42-0".to_string(),
+            members: Vec::new(),
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_secs(2),
+        };
+        let relaunch = LaunchRecord {
+            primary_pid: 5678,
+            job: "game#42-1".to_string(),
+            ..record.clone()
+        };
+        table.insert("game".to_string(), relaunch.clone()).unwrap();
+
+        table.remove_record("game", &record.job).unwrap();
+        assert_eq!(table.load().unwrap().get("game"), Some(&relaunch));
+        table.remove_record("game", &relaunch.job).unwrap();
+        assert!(table.load().unwrap().is_empty());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn recovers_only_the_processes_it_recorded() {
+        let mut child = testing::paused_child();
+        let id = testing::unique_name("recover");
+        let member = Member::of(child.id()).unwrap();
+        let record = LaunchRecord {
+            command: Command {
+                executable: "cmd".to_string(),
+                command_line: "cmd /c pause".to_string(),
+            },
+            primary_pid: child.id(),
+            job: id.clone(),
+            members: vec![member],
+            started_at: SystemTime::now(),
+        };
+
+        let reused = LaunchRecord {
+            members: vec![Member {
+                created: member.created - Duration::from_secs(1),
+                ..member
+            }],
+            ..record.clone()
+        };
+        assert_eq!(
+            Recovered::adopt(&id, reused)
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_NOT_FOUND.0))
+        );
+
+        let recovered = Recovered::adopt(&id, record).unwrap();
+        assert_eq!(recovered.job.process_ids().unwrap(), [child.id()]);
+
+        recovered.job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+}
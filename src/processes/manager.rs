@@ -1,110 +1,1520 @@
-use std::{ffi::OsStr, os::windows::ffi::OsStrExt, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
-use super::process::{Process, ProcessInfo, ProcessSnapshot};
-use next_proto::winebridge;
+use super::accounting::{self, History, Run};
+use super::command_line;
+use super::console;
+use super::crash;
+use super::environment;
+use super::events::EventHub;
+use super::job::{JOB_POLL_INTERVAL, Job};
+use super::launch::{Command, ExitStatus, Launch};
+use super::launch_table::{LaunchRecord, LaunchTable, Recovered};
+use super::module::{Module, ModuleSnapshot};
+use super::monitor;
+use super::pipe::{Pipe, PipeHandle};
+use super::process::{Process, ProcessHandle, ProcessInfo, ProcessSnapshot};
+use super::pseudo_console::{AttributeList, PseudoConsole};
+use super::session::ConsoleSession;
+use super::shell;
+use super::stack::{self, ThreadStack};
+use super::thread::{Thread, ThreadSnapshot};
+use super::to_wide_string;
+use super::watchdog;
+use crate::desktop::manager::WindowManager;
+use next_proto::winebridge::{
+    self, console_output::Event as ConsoleEvent, process_output::Event, process_target::Target,
+};
+use tokio::sync::mpsc;
 use windows::{
     Win32::{
-        Foundation::{CloseHandle, HANDLE},
-        System::{
-            JobObjects::{AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject},
-            Threading::{
-                CREATE_NEW_CONSOLE, CREATE_SUSPENDED, CreateProcessW, PROCESS_CREATION_FLAGS,
-                ResumeThread, STARTUPINFOW, TerminateProcess,
-            },
+        Foundation::{ERROR_ALREADY_EXISTS, ERROR_NOT_FOUND, ERROR_TIMEOUT, HANDLE},
+        System::Threading::{
+            CREATE_NEW_CONSOLE, CREATE_SUSPENDED, CREATE_UNICODE_ENVIRONMENT, CreateProcessW,
+            EXTENDED_STARTUPINFO_PRESENT, PROCESS_CREATION_FLAGS, PROCESS_SET_INFORMATION,
+            ResumeThread, STARTF_USESTDHANDLES, STARTUPINFOEXW, STARTUPINFOW, TerminateProcess,
         },
     },
-    core::{Error, PCWSTR, PWSTR},
+    core::{Error, HRESULT, PCWSTR, PWSTR},
 };
 
-fn to_wide_string(s: impl AsRef<OsStr>) -> Vec<u16> {
-    s.as_ref().encode_wide().chain(Some(0)).collect()
+/// Size of one chunk forwarded from a captured stdout/stderr pipe.
+const OUTPUT_CHUNK: usize = 16 * 1024;
+
+/// Finished launches kept for clients to wait on or look up, beyond which the
+/// oldest ones are forgotten along with the handles they hold.
+const FINISHED_LAUNCHES: usize = 64;
+
+/// Serializes launches that hand inheritable pipe ends to a child, so one
+/// child never inherits the pipes meant for another launched concurrently
+/// (which would keep those pipes open, and their readers waiting, for as
+/// long as the wrong child lives).
+static INHERITANCE: Mutex<()> = Mutex::new(());
+
+/// The child's ends of the pipes replacing its standard handles, or the
+/// attributes attaching it to a pseudo console instead.
+#[derive(Default)]
+struct Stdio {
+    input: Option<PipeHandle>,
+    output: Option<PipeHandle>,
+    error: Option<PipeHandle>,
+    attributes: Option<AttributeList>,
 }
 
-struct Job(HANDLE);
+impl Stdio {
+    fn is_redirected(&self) -> bool {
+        self.input.is_some() || self.output.is_some() || self.error.is_some()
+    }
 
-impl Job {
-    fn open(id: &str) -> Result<Self, Error> {
-        let name = to_wide_string(id);
-        Ok(Self(unsafe {
-            CreateJobObjectW(None, PCWSTR(name.as_ptr()))?
-        }))
+    fn handles(&self) -> [Option<&PipeHandle>; 3] {
+        [
+            self.input.as_ref(),
+            self.output.as_ref(),
+            self.error.as_ref(),
+        ]
     }
 }
 
-impl Drop for Job {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = CloseHandle(self.0);
+/// Forwards everything written to `pipe` to `events`, wrapping each chunk
+/// with `event`, until the child closes its end or the receiver goes away.
+fn forward_output<T: Send + 'static>(
+    pipe: PipeHandle,
+    events: mpsc::Sender<T>,
+    event: impl Fn(Vec<u8>) -> T + Send + 'static,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buffer = vec![0u8; OUTPUT_CHUNK];
+        loop {
+            let read = match pipe.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) => {
+                    tracing::warn!("Failed to read output of a launched process: {error}");
+                    break;
+                }
+            };
+            if events
+                .blocking_send(event(buffer[..read].to_vec()))
+                .is_err()
+            {
+                break;
+            }
         }
+    })
+}
+
+fn console_data(data: Vec<u8>) -> winebridge::ConsoleOutput {
+    winebridge::ConsoleOutput {
+        event: Some(ConsoleEvent::Data(data)),
     }
 }
 
-pub struct ProcessManager;
+/// Wraps a chunk read from a captured stdout or stderr pipe.
+fn output_chunk(stream: winebridge::OutputStream) -> impl Fn(Vec<u8>) -> winebridge::ProcessOutput {
+    move |data| winebridge::ProcessOutput {
+        event: Some(Event::Chunk(winebridge::ProcessOutputChunk {
+            stream: stream as i32,
+            data,
+        })),
+    }
+}
 
-impl ProcessManager {
-    pub fn running_processes(&self) -> Result<Vec<Process>, Error> {
-        Ok(ProcessSnapshot::new()?.collect())
+/// Applies the limits and scheduling `request` asks for to `job`, before
+/// any of the launch's processes join it.
+fn configure(job: &Job, request: &winebridge::LaunchProcessRequest) -> Result<(), Error> {
+    if let Some(limits) = &request.limits {
+        job.set_limits(limits)?;
+    }
+    if let Some(class) = super::priority_class(request.priority()) {
+        job.set_priority_class(class)?;
     }
+    if let Some(mask) = request.affinity_mask {
+        job.set_affinity(mask)?;
+    }
+    job.set_kill_on_close(dies_with_bridge(request))
+}
 
-    pub fn execute(&self, request: winebridge::LaunchProcessRequest) -> Result<u32, Error> {
-        let job = Job::open(&request.id)?;
-        let executable = PathBuf::from(request.executable);
-        let command_line = std::iter::once(executable.display().to_string())
-            .chain(request.arguments)
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        let executable_w = to_wide_string(executable.as_os_str());
-        let mut command_line = to_wide_string(command_line);
-        // Keep the wide-encoded working directory alive for the whole CreateProcessW
-        // call: the PCWSTR below borrows this buffer, so it must outlive the call.
-        let work_dir_w = request.working_directory.map(to_wide_string);
-        let work_dir = work_dir_w
+/// Whether the processes of the launch `request` asks for die with the
+/// bridge, through its lifecycle policy or its job limits.
+fn dies_with_bridge(request: &winebridge::LaunchProcessRequest) -> bool {
+    request.lifecycle() == winebridge::LaunchLifecycle::KillOnBridgeExit
+        || request
+            .limits
             .as_ref()
-            .map(|work_dir| PCWSTR(work_dir.as_ptr()))
-            .unwrap_or_else(PCWSTR::null);
-        let flags = CREATE_SUSPENDED
-            | if request.new_console {
-                CREATE_NEW_CONSOLE
-            } else {
-                PROCESS_CREATION_FLAGS(0)
+            .is_some_and(|limits| limits.kill_on_job_close)
+}
+
+/// Starts watching the primary process of `launch` for crashes if the launch
+/// asked for minidumps, which runs it under a debugger as described on
+/// [`crash::watch`]. Failing to attach only costs the dumps, so it is logged
+/// rather than failing the launch.
+fn watch_crashes(launch: &Arc<Launch>, request: &winebridge::LaunchProcessRequest) {
+    let Some(directory) = &request.crash_dump_directory else {
+        return;
+    };
+    if let Err(error) = crash::watch(launch.clone(), request.id.clone(), directory.into()) {
+        tracing::warn!(
+            "Failed to watch process {} of launch {} for crashes: {error}",
+            launch.process.pid(),
+            request.id
+        );
+    }
+}
+
+fn start_watchdog(launch: &Arc<Launch>, request: &winebridge::LaunchProcessRequest) {
+    let timeout = request
+        .timeout_ms
+        .map(|ms| Duration::from_millis(ms.into()));
+    let idle_timeout = request
+        .idle_timeout_ms
+        .map(|ms| Duration::from_millis(ms.into()));
+    if timeout.is_some() || idle_timeout.is_some() {
+        watchdog::watch(launch.clone(), timeout, idle_timeout);
+    }
+}
+
+/// Reads `pipe` to its end, keeping the first `limit` bytes and discarding
+/// the rest, so the child never blocks on a full pipe.
+fn collect_output(pipe: PipeHandle, limit: usize) -> std::thread::JoinHandle<(Vec<u8>, bool)> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let mut truncated = false;
+        let mut buffer = vec![0u8; OUTPUT_CHUNK];
+        loop {
+            let read = match pipe.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) => {
+                    tracing::warn!("Failed to read output of a launched process: {error}");
+                    break;
+                }
             };
-        let startup_info = STARTUPINFOW {
+            let kept = read.min(limit - output.len());
+            output.extend_from_slice(&buffer[..kept]);
+            truncated |= kept < read;
+        }
+        (output, truncated)
+    })
+}
+
+/// Creates the process `request` asks for inside `job`, with `stdio`
+/// replacing its standard handles, and lets it run. Attaching to the job and
+/// the crash watcher before it runs keeps anything it does from escaping
+/// either.
+fn create(
+    job: Job,
+    request: &winebridge::LaunchProcessRequest,
+    mut stdio: Stdio,
+) -> Result<Arc<Launch>, Error> {
+    let stdin = if request.redirect_stdin {
+        let pipe = Pipe::new()?;
+        stdio.input = Some(pipe.read);
+        Some(pipe.write)
+    } else {
+        None
+    };
+    let command_line = match &request.command_line {
+        Some(raw) => command_line::build_raw(&request.executable, raw),
+        None => command_line::build(&request.executable, &request.arguments),
+    };
+
+    let executable_w = to_wide_string(&request.executable);
+    let command = Command {
+        executable: request.executable.clone(),
+        command_line,
+    };
+    let mut command_line = to_wide_string(&command.command_line);
+    // Keep the wide-encoded working directory alive for the whole CreateProcessW
+    // call: the PCWSTR below borrows this buffer, so it must outlive the call.
+    let work_dir_w = request.working_directory.as_deref().map(to_wide_string);
+    let work_dir = work_dir_w
+        .as_ref()
+        .map(|work_dir| PCWSTR(work_dir.as_ptr()))
+        .unwrap_or_else(PCWSTR::null);
+    // Programs inherit the bridge's own environment unless the launch
+    // customizes it, in which case they get a block of their own.
+    let environment = (request.clean_environment
+        || !request.environment.is_empty()
+        || !request.unset_environment.is_empty())
+    .then(|| {
+        let base = (!request.clean_environment)
+            .then(std::env::vars_os)
+            .into_iter()
+            .flatten();
+        environment::block(base, &request.environment, &request.unset_environment)
+    });
+    let mut flags = CREATE_SUSPENDED
+        | CREATE_UNICODE_ENVIRONMENT
+        | if request.new_console {
+            CREATE_NEW_CONSOLE
+        } else {
+            PROCESS_CREATION_FLAGS(0)
+        };
+    let mut startup_info = STARTUPINFOEXW {
+        StartupInfo: STARTUPINFOW {
             cb: std::mem::size_of::<STARTUPINFOW>() as u32,
             ..Default::default()
-        };
-        let mut process_info = ProcessInfo::default();
+        },
+        ..Default::default()
+    };
+    if let Some(attributes) = &stdio.attributes {
+        flags |= EXTENDED_STARTUPINFO_PRESENT;
+        startup_info.StartupInfo.cb = std::mem::size_of::<STARTUPINFOEXW>() as u32;
+        startup_info.lpAttributeList = attributes.raw();
+    }
+    let redirected = stdio.is_redirected();
+    if redirected {
+        // Handles that are not redirected stay null: the bridge runs
+        // without a console, so it has no standard handles to pass on.
+        // Stdin is therefore only redirected along with the output, as
+        // the requests reaching here are checked to ensure.
+        let startup_info = &mut startup_info.StartupInfo;
+        startup_info.dwFlags |= STARTF_USESTDHANDLES;
+        startup_info.hStdInput = stdio
+            .input
+            .as_ref()
+            .map_or_else(HANDLE::default, PipeHandle::raw);
+        startup_info.hStdOutput = stdio
+            .output
+            .as_ref()
+            .map_or_else(HANDLE::default, PipeHandle::raw);
+        startup_info.hStdError = stdio
+            .error
+            .as_ref()
+            .map_or_else(HANDLE::default, PipeHandle::raw);
+    }
+    let mut process_info = ProcessInfo::default();
 
-        unsafe {
+    unsafe {
+        {
+            let _inheritance =
+                redirected.then(|| INHERITANCE.lock().unwrap_or_else(PoisonError::into_inner));
+            for handle in stdio.handles().into_iter().flatten() {
+                handle.set_inheritable(true)?;
+            }
             CreateProcessW(
                 PCWSTR(executable_w.as_ptr()),
                 Some(PWSTR(command_line.as_mut_ptr())),
                 None,
                 None,
-                false,
+                redirected,
                 flags,
-                None,
+                environment
+                    .as_ref()
+                    .map(|environment| environment.as_ptr() as *const _),
                 work_dir,
-                &startup_info,
+                &startup_info.StartupInfo,
                 &mut process_info.0,
             )?;
+            // The child holds its own copies now; closing ours lets the
+            // bridge's readers see end-of-file once the child exits.
+            drop(stdio);
+        }
 
-            if let Err(error) = AssignProcessToJobObject(job.0, process_info.0.hProcess) {
-                let _ = TerminateProcess(process_info.0.hProcess, 1);
-                return Err(error);
+        if let Err(error) = job.assign(process_info.0.hProcess) {
+            let _ = TerminateProcess(process_info.0.hProcess, 1);
+            return Err(error);
+        }
+    }
+
+    let launch = Arc::new(Launch::new(job, process_info, command, stdin));
+    // Attaching before the process runs catches crashes during startup.
+    watch_crashes(&launch, request);
+    unsafe {
+        if ResumeThread(launch.process.0.hThread) == u32::MAX {
+            let error = Error::from_thread();
+            let _ = TerminateProcess(launch.process.0.hProcess, 1);
+            return Err(error);
+        }
+    }
+
+    Ok(launch)
+}
+
+/// Waits for `launch`, whose stdout and stderr are written into `stdout`
+/// and `stderr`, collecting up to `output_limit` bytes of each, and
+/// terminates it past `timeout`; see [`ProcessManager::run`].
+fn collect_run(
+    launch: &Launch,
+    stdout: PipeHandle,
+    stderr: PipeHandle,
+    timeout: Option<Duration>,
+    output_limit: usize,
+) -> Result<RunOutput, Error> {
+    // Nobody will type anything, so a redirected stdin reads end-of-file.
+    launch.close_stdin();
+    let readers = [
+        collect_output(stdout, output_limit),
+        collect_output(stderr, output_limit),
+    ];
+
+    let (exit, timed_out) = match launch.wait(timeout) {
+        Err(error) if error.code() == HRESULT::from_win32(ERROR_TIMEOUT.0) => {
+            launch.job.terminate(watchdog::WATCHDOG_EXIT_CODE)?;
+            (launch.wait(None)?, true)
+        }
+        exit => (exit?, false),
+    };
+    let [(stdout, stdout_truncated), (stderr, stderr_truncated)] =
+        readers.map(|reader| reader.join().unwrap_or_default());
+
+    Ok(RunOutput {
+        exit,
+        stdout,
+        stderr,
+        truncated: stdout_truncated || stderr_truncated,
+        timed_out,
+    })
+}
+
+/// Asks every process of `job` to exit, then terminates the ones left after
+/// `grace_period`; see [`ProcessManager::stop`].
+///
+/// Only failing to terminate the job fails the stop. When its processes
+/// cannot be listed, nothing can be asked to exit, so the job is terminated
+/// right away and the report lists no pids.
+fn stop_job(job: &Job, grace_period: Duration) -> Result<StopReport, Error> {
+    let pids = match job.process_ids() {
+        Ok(pids) => pids,
+        Err(error) => {
+            tracing::warn!("Failed to list the processes of a launch to stop: {error}");
+            job.terminate(1)?;
+            return Ok(StopReport {
+                exited: Vec::new(),
+                killed: Vec::new(),
+            });
+        }
+    };
+
+    // Only the windows a user would close: hidden ones include IME, DDE
+    // and message-only windows, and owned ones are dialogs that close
+    // along with their owner.
+    match WindowManager.list() {
+        Ok(windows) => {
+            for window in windows {
+                if pids.contains(&window.pid)
+                    && window.is_visible()
+                    && !window.is_owned()
+                    && let Err(error) = WindowManager.close(&window)
+                {
+                    tracing::debug!(
+                        "Failed to close a window of process {}: {error}",
+                        window.pid
+                    );
+                }
             }
-            if ResumeThread(process_info.0.hThread) == u32::MAX {
-                let error = Error::from_thread();
+        }
+        Err(error) => tracing::warn!("Failed to list the windows of a launch to stop: {error}"),
+    }
+    interrupt_consoles(&pids, false);
+
+    // Consoles still running halfway through get Ctrl+Break, which they
+    // cannot ignore the way they can Ctrl+C.
+    let remaining = wait_for_exit(job, pids.clone(), grace_period / 2);
+    interrupt_consoles(&remaining, true);
+    let remaining = wait_for_exit(job, remaining, grace_period - grace_period / 2);
+    if !remaining.is_empty() {
+        job.terminate(1)?;
+    }
+
+    Ok(StopReport {
+        exited: pids
+            .into_iter()
+            .filter(|pid| !remaining.contains(pid))
+            .collect(),
+        killed: remaining,
+    })
+}
+
+fn interrupt_consoles(pids: &[u32], hard: bool) {
+    for &pid in pids {
+        // Most processes, GUI ones in particular, have no console.
+        let _ = console::interrupt(pid, hard);
+    }
+}
+
+/// Polls `job` until it has no processes left or `timeout` has passed, and
+/// returns the ones still running. A failed query is logged and keeps the
+/// processes last seen, so they are still terminated in the end.
+fn wait_for_exit(job: &Job, mut remaining: Vec<u32>, timeout: Duration) -> Vec<u32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match job.process_ids() {
+            Ok(pids) => remaining = pids,
+            Err(error) => {
+                tracing::warn!("Failed to list the processes of a launch to stop: {error}");
+            }
+        }
+        if remaining.is_empty() || Instant::now() >= deadline {
+            return remaining;
+        }
+        std::thread::sleep(JOB_POLL_INTERVAL);
+    }
+}
+
+/// Forgets the oldest finished launches beyond [`FINISHED_LAUNCHES`].
+fn evict_finished(launches: &mut HashMap<String, Arc<Launch>>) {
+    let mut finished: Vec<_> = launches
+        .iter()
+        .filter(|(_, launch)| {
+            launch
+                .job
+                .active_processes()
+                .is_ok_and(|active| active == 0)
+        })
+        .map(|(id, launch)| (launch.started, id.clone()))
+        .collect();
+    if finished.len() <= FINISHED_LAUNCHES {
+        return;
+    }
+    finished.sort_unstable();
+    for (_, id) in &finished[..finished.len() - FINISHED_LAUNCHES] {
+        launches.remove(id);
+    }
+}
+
+/// Outcome of [`ProcessManager::run`].
+pub struct RunOutput {
+    pub exit: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// Whether either stream was cut off at the output limit.
+    pub truncated: bool,
+    /// Whether the launch was terminated for running longer than the timeout.
+    pub timed_out: bool,
+}
+
+/// Outcome of [`ProcessManager::stop`].
+pub struct StopReport {
+    /// Processes that exited on their own within the grace period.
+    pub exited: Vec<u32>,
+    /// Processes still running after the grace period, terminated with the job.
+    pub killed: Vec<u32>,
+}
+
+/// A launch id claimed by [`ProcessManager::reserve`] for a launch about to
+/// start, released once the launch is registered or fails to start.
+pub struct Reservation<'a> {
+    starting: &'a Mutex<HashSet<String>>,
+    id: String,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.starting
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+/// Launches programs into a job object per run and remembers them, so later
+/// calls can wait on or inspect a launch by its id.
+#[derive(Default)]
+pub struct ProcessManager {
+    launches: Mutex<HashMap<String, Arc<Launch>>>,
+    /// Ids reserved for launches that are starting. Only ever locked while
+    /// `launches` is, or on its own.
+    starting: Mutex<HashSet<String>>,
+    /// Launches of a previous bridge instance still running, until their
+    /// processes have exited or the same id is launched again.
+    recovered: Arc<Mutex<HashMap<String, Arc<Recovered>>>>,
+    /// Launches that outlive the bridge, each until its job empties.
+    table: Arc<LaunchTable>,
+    /// Created with the first launch or subscription, whichever comes first.
+    events: Mutex<Option<Arc<EventHub>>>,
+    history: Arc<History>,
+}
+
+impl ProcessManager {
+    /// A manager that already knows the launches a previous bridge instance
+    /// left running, from the launch table.
+    pub fn new() -> Self {
+        let manager = Self::default();
+        manager.recover();
+        manager
+    }
+
+    /// A manager keeping its launch table and history in `directory` rather
+    /// than the prefix, recovering the launches recorded there.
+    #[cfg(test)]
+    pub fn in_directory(directory: &std::path::Path) -> Self {
+        let manager = Self {
+            table: Arc::new(LaunchTable::new(
+                directory.join(super::launch_table::TABLE_FILE),
+            )),
+            history: Arc::new(History::new(directory.join(accounting::HISTORY_FILE))),
+            ..Self::default()
+        };
+        manager.recover();
+        manager
+    }
+
+    /// Finds the processes of every launch in the launch table again and
+    /// keeps the launches still running, dropping the rest from the table.
+    fn recover(&self) {
+        let records = match self.table.load() {
+            Ok(records) => records,
+            Err(error) => {
+                tracing::warn!("Failed to read the launch table: {error}");
+                return;
+            }
+        };
+
+        let mut recovered = HashMap::new();
+        for (id, record) in records {
+            match Recovered::adopt(&id, record) {
+                Ok(launch) => {
+                    recovered.insert(id, Arc::new(launch));
+                }
+                Err(error) => tracing::debug!("Launch {id} is no longer running: {error}"),
+            }
+        }
+        if !recovered.is_empty() {
+            tracing::info!(
+                "Recovered {} launches of a previous bridge instance",
+                recovered.len()
+            );
+        }
+
+        let records = recovered
+            .iter()
+            .map(|(id, recovered)| (id.clone(), recovered.record.clone()))
+            .collect();
+        if let Err(error) = self.table.replace(records) {
+            tracing::warn!("Failed to save the launch table: {error}");
+        }
+        let mut launches = self
+            .recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for (id, launch) in &recovered {
+            self.watch_recovered(id, launch);
+        }
+        *launches = recovered;
+    }
+
+    /// Waits on a dedicated thread for the job of the recovered `launch` to
+    /// empty, then forgets it and drops it from the launch table. Nothing
+    /// else waits on a recovered launch, so this is where it ends.
+    fn watch_recovered(&self, id: &str, launch: &Arc<Recovered>) {
+        let id = id.to_string();
+        let launch = launch.clone();
+        let recovered = self.recovered.clone();
+        let table = self.table.clone();
+        std::thread::spawn(move || {
+            while launch.job.active_processes().is_ok_and(|active| active > 0) {
+                std::thread::sleep(JOB_POLL_INTERVAL);
+            }
+            let mut recovered = recovered.lock().unwrap_or_else(PoisonError::into_inner);
+            // A relaunch of the id replaces the recovered launch, which must
+            // then be left alone.
+            if recovered
+                .get(&id)
+                .is_some_and(|current| Arc::ptr_eq(current, &launch))
+            {
+                recovered.remove(&id);
+            }
+            drop(recovered);
+            if let Err(error) = table.remove_record(&id, &launch.record.job) {
+                tracing::warn!("Failed to drop launch {id} from the launch table: {error}");
+            }
+        });
+    }
+
+    pub fn running_processes(&self) -> Result<Vec<Process>, Error> {
+        Ok(ProcessSnapshot::new()?.collect())
+    }
+
+    pub fn threads(&self, pid: u32) -> Result<Vec<Thread>, Error> {
+        Ok(ThreadSnapshot::new()?
+            .filter(|thread| thread.owner_pid() == pid)
+            .collect())
+    }
+
+    pub fn modules(&self, pid: u32) -> Result<Vec<Module>, Error> {
+        Ok(ModuleSnapshot::new(pid)?.collect())
+    }
+
+    /// Stack traces of every thread of process `pid`, for diagnosing hangs.
+    pub fn stacks(&self, pid: u32, max_frames: usize) -> Result<Vec<ThreadStack>, Error> {
+        stack::capture(pid, max_frames)
+    }
+
+    /// Claims the id of `request` for a launch about to start, failing with
+    /// `ERROR_ALREADY_EXISTS` while another launch of that id is starting, or
+    /// is still running and the request does not join it.
+    ///
+    /// Every launch starts from a reservation, so checking the id and
+    /// registering the launch cannot interleave with another launch.
+    pub fn reserve(
+        &self,
+        request: &winebridge::LaunchProcessRequest,
+    ) -> Result<Reservation<'_>, Error> {
+        let launches = self.launches.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut starting = self.starting.lock().unwrap_or_else(PoisonError::into_inner);
+        let running = |job: &Job| job.active_processes().is_ok_and(|active| active > 0);
+        let busy = starting.contains(&request.id)
+            || !request.join_existing
+                && (launches
+                    .get(&request.id)
+                    .is_some_and(|launch| running(&launch.job))
+                    || self
+                        .recovered
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .get(&request.id)
+                        .is_some_and(|recovered| running(&recovered.job)));
+        if busy {
+            return Err(Error::from_hresult(HRESULT::from_win32(
+                ERROR_ALREADY_EXISTS.0,
+            )));
+        }
+        starting.insert(request.id.clone());
+        Ok(Reservation {
+            starting: &self.starting,
+            id: request.id.clone(),
+        })
+    }
+
+    /// Opens the job of the launch `request` asks for, configured by
+    /// [`configure`], and returns whether it joined a running launch.
+    ///
+    /// A launch of an id still running joins that run's job when the request
+    /// says so, and fails with `ERROR_ALREADY_EXISTS` otherwise. Every other
+    /// launch gets a fresh job, so nothing an earlier run of the id set or
+    /// used carries over.
+    fn open_job(&self, request: &winebridge::LaunchProcessRequest) -> Result<(Job, bool), Error> {
+        let (job, joined) = match self.running_job(&request.id) {
+            Some(_) if !request.join_existing => {
+                return Err(Error::from_hresult(HRESULT::from_win32(
+                    ERROR_ALREADY_EXISTS.0,
+                )));
+            }
+            Some(name) => (Job::open(&name)?, true),
+            None => (Job::create(&request.id)?, false),
+        };
+        configure(&job, request)?;
+        Ok((job, joined))
+    }
+
+    /// Name of the job of launch `id`, launched or recovered, while any of
+    /// its processes are running.
+    fn running_job(&self, id: &str) -> Option<String> {
+        let running = |job: &Job| {
+            job.active_processes()
+                .is_ok_and(|active| active > 0)
+                .then(|| job.name().map(str::to_string))
+                .flatten()
+        };
+        if let Ok(launch) = self.launch(id)
+            && let Some(name) = running(&launch.job)
+        {
+            return Some(name);
+        }
+        self.recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .and_then(|recovered| running(&recovered.job))
+    }
+
+    /// Starts the launch `request` asks for, returning `None` for a shell
+    /// launch that started no new process.
+    pub fn execute(
+        &self,
+        reservation: Reservation<'_>,
+        request: winebridge::LaunchProcessRequest,
+    ) -> Result<Option<Arc<Launch>>, Error> {
+        if request.mode() == winebridge::LaunchMode::ShellExecute {
+            return self.shell_execute(reservation, request);
+        }
+        self.spawn(reservation, request, Stdio::default()).map(Some)
+    }
+
+    /// Opens the request's executable through the shell and adds the process
+    /// it started to the launch's job, returning `None` when the shell
+    /// started no new process.
+    ///
+    /// Unlike [`Self::spawn`], the process is already running by the time it
+    /// joins the job, so anything it starts before that escapes the job.
+    fn shell_execute(
+        &self,
+        reservation: Reservation<'_>,
+        request: winebridge::LaunchProcessRequest,
+    ) -> Result<Option<Arc<Launch>>, Error> {
+        let (job, joined) = self.open_job(&request)?;
+        self.watch_events(&job, &request.id);
+        let parameters = match &request.command_line {
+            Some(raw) => raw.clone(),
+            None => command_line::arguments(&request.arguments),
+        };
+
+        let Some(process_info) = shell::execute(
+            &request.executable,
+            request.verb.as_deref(),
+            &parameters,
+            request.working_directory.as_deref(),
+            request.new_console,
+        )?
+        else {
+            return Ok(None);
+        };
+        if let Err(error) = job.assign(process_info.0.hProcess) {
+            unsafe {
                 let _ = TerminateProcess(process_info.0.hProcess, 1);
-                return Err(error);
             }
+            return Err(error);
+        }
+
+        let command = Command {
+            command_line: command_line::build_raw(&request.executable, &parameters),
+            executable: request.executable.clone(),
+        };
+        let launch = Arc::new(Launch::new(job, process_info, command, None));
+        watch_crashes(&launch, &request);
+        start_watchdog(&launch, &request);
+        let record = self.register(reservation, &request, launch.clone());
+        self.watch_exit(&launch, &request.id, joined, record);
+        Ok(Some(launch))
+    }
+
+    /// Launches like [`Self::execute`], but with stdout and stderr redirected
+    /// into pipes whose contents are sent to `events`, followed by the exit
+    /// code once the process has ended and both pipes are drained.
+    pub fn execute_with_output(
+        &self,
+        reservation: Reservation<'_>,
+        request: winebridge::LaunchProcessRequest,
+        events: mpsc::Sender<winebridge::ProcessOutput>,
+    ) -> Result<Arc<Launch>, Error> {
+        let stdout = Pipe::new()?;
+        let stderr = Pipe::new()?;
+        let launch = self.spawn(
+            reservation,
+            request,
+            Stdio {
+                output: Some(stdout.write),
+                error: Some(stderr.write),
+                ..Default::default()
+            },
+        )?;
+        let pid = launch.process.pid();
+
+        let watched = launch.clone();
+        std::thread::spawn(move || {
+            let started = winebridge::ProcessOutput {
+                event: Some(Event::Started(pid)),
+            };
+            if events.blocking_send(started).is_err() {
+                return;
+            }
+
+            let readers = [
+                forward_output(
+                    stdout.read,
+                    events.clone(),
+                    output_chunk(winebridge::OutputStream::Stdout),
+                ),
+                forward_output(
+                    stderr.read,
+                    events.clone(),
+                    output_chunk(winebridge::OutputStream::Stderr),
+                ),
+            ];
+            for reader in readers {
+                let _ = reader.join();
+            }
+
+            match launch.process.wait(None) {
+                Ok(exit_code) => {
+                    let _ = events.blocking_send(winebridge::ProcessOutput {
+                        event: Some(Event::Exited(exit_code)),
+                    });
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to wait for launched process {pid}: {error}");
+                }
+            }
+        });
+
+        Ok(watched)
+    }
+
+    /// Launches a console program attached to a pseudo console of
+    /// `columns` x `rows` cells, or to plain pipes where pseudo consoles are
+    /// unavailable, and relays everything it writes to `events`.
+    ///
+    /// `events` receives the pid first and the exit code last, once the
+    /// program has ended and its output is drained.
+    pub fn open_console(
+        &self,
+        reservation: Reservation<'_>,
+        mut request: winebridge::LaunchProcessRequest,
+        columns: u16,
+        rows: u16,
+        events: mpsc::Sender<winebridge::ConsoleOutput>,
+    ) -> Result<Arc<ConsoleSession>, Error> {
+        let input = Pipe::new()?;
+        let output = Pipe::new()?;
+        let (launch, console, errors) =
+            match PseudoConsole::new(columns, rows, &input.read, &output.write) {
+                Ok(console) => {
+                    // The console owns the program's console window.
+                    request.new_console = false;
+                    let stdio = Stdio {
+                        attributes: Some(console.attributes()?),
+                        ..Default::default()
+                    };
+                    let launch = self.spawn(reservation, request, stdio)?;
+                    // The console holds its own copies of its ends, so dropping
+                    // ours lets the reader see end-of-file once it closes.
+                    drop(input.read);
+                    drop(output.write);
+                    (launch, Some(console), None)
+                }
+                Err(error) => {
+                    tracing::info!("Pseudo consoles are unavailable, using pipes instead: {error}");
+                    let errors = Pipe::new()?;
+                    let stdio = Stdio {
+                        input: Some(input.read),
+                        output: Some(output.write),
+                        error: Some(errors.write),
+                        ..Default::default()
+                    };
+                    let launch = self.spawn(reservation, request, stdio)?;
+                    (launch, None, Some(errors.read))
+                }
+            };
+        let session = Arc::new(ConsoleSession::new(launch, input.write, console));
+
+        let pid = session.launch.process.pid();
+        let started = winebridge::ConsoleOutput {
+            event: Some(ConsoleEvent::Started(winebridge::ConsoleStarted {
+                pid,
+                pseudo_console: session.is_pseudo_console(),
+            })),
+        };
+        let coordinator = session.clone();
+        std::thread::spawn(move || {
+            if events.blocking_send(started).is_err() {
+                return;
+            }
+            let readers: Vec<_> = [Some(output.read), errors]
+                .into_iter()
+                .flatten()
+                .map(|pipe| forward_output(pipe, events.clone(), console_data))
+                .collect();
+
+            let exit_code = coordinator.launch.process.wait(None);
+            // A pseudo console outlives the program attached to it, so its
+            // output only ends once it is closed.
+            coordinator.close_console();
+            for reader in readers {
+                let _ = reader.join();
+            }
+
+            match exit_code {
+                Ok(exit_code) => {
+                    let _ = events.blocking_send(winebridge::ConsoleOutput {
+                        event: Some(ConsoleEvent::Exited(exit_code)),
+                    });
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to wait for console process {pid}: {error}");
+                }
+            }
+        });
+
+        Ok(session)
+    }
+
+    /// Launches a program, collects up to `output_limit` bytes of each of its
+    /// stdout and stderr, and blocks until every process of the launch has
+    /// exited. Past `timeout`, the whole launch is terminated.
+    pub fn run(
+        &self,
+        reservation: Reservation<'_>,
+        request: winebridge::LaunchProcessRequest,
+        timeout: Option<Duration>,
+        output_limit: usize,
+    ) -> Result<RunOutput, Error> {
+        let stdout = Pipe::new()?;
+        let stderr = Pipe::new()?;
+        let launch = self.spawn(
+            reservation,
+            request,
+            Stdio {
+                output: Some(stdout.write),
+                error: Some(stderr.write),
+                ..Default::default()
+            },
+        )?;
+        collect_run(&launch, stdout.read, stderr.read, timeout, output_limit)
+    }
+
+    /// Runs a program like [`Self::run`], but in a job of its own that no
+    /// launch id names and that dies with the bridge. Nothing remembers it:
+    /// it is neither listed, accounted for nor reported as events, and it
+    /// never takes an id a client might launch.
+    pub fn run_unregistered(
+        &self,
+        request: winebridge::LaunchProcessRequest,
+        timeout: Option<Duration>,
+        output_limit: usize,
+    ) -> Result<RunOutput, Error> {
+        let job = Job::anonymous()?;
+        job.set_kill_on_close(true)?;
+        let stdout = Pipe::new()?;
+        let stderr = Pipe::new()?;
+        let launch = create(
+            job,
+            &request,
+            Stdio {
+                output: Some(stdout.write),
+                error: Some(stderr.write),
+                ..Default::default()
+            },
+        )?;
+        collect_run(&launch, stdout.read, stderr.read, timeout, output_limit)
+    }
+
+    /// Samples every running process each `interval` on a dedicated thread,
+    /// until `samples` is closed.
+    pub fn monitor(&self, interval: Duration, samples: mpsc::Sender<winebridge::ProcessSample>) {
+        std::thread::spawn(move || monitor::run(interval, samples));
+    }
+
+    /// Blocks until every process of launch `id` has exited, returning the
+    /// exit code of the process the launch started.
+    pub fn wait(&self, id: &str, timeout: Option<Duration>) -> Result<ExitStatus, Error> {
+        self.launch(id)?.wait(timeout)
+    }
+
+    pub fn write_stdin(&self, id: &str, data: &[u8]) -> Result<(), Error> {
+        self.launch(id)?.write_stdin(data)
+    }
+
+    pub fn close_stdin(&self, id: &str) -> Result<(), Error> {
+        self.launch(id)?.close_stdin();
+        Ok(())
+    }
+
+    pub fn suspend(&self, id: &str) -> Result<(), Error> {
+        self.launch(id)?.suspend()
+    }
+
+    pub fn resume(&self, id: &str) -> Result<(), Error> {
+        self.launch(id)?.resume()
+    }
+
+    /// Sets the priority class of a single process, or of every process of a
+    /// launch including those it starts later.
+    pub fn set_priority(
+        &self,
+        target: &Target,
+        class: PROCESS_CREATION_FLAGS,
+    ) -> Result<(), Error> {
+        match target {
+            Target::Pid(pid) => {
+                ProcessHandle::open(*pid, PROCESS_SET_INFORMATION)?.set_priority_class(class)
+            }
+            Target::Id(id) => self.launch(id)?.job.set_priority_class(class),
+        }
+    }
+
+    /// Sets the CPU affinity of a single process, or of every process of a
+    /// launch including those it starts later.
+    pub fn set_affinity(&self, target: &Target, mask: u64) -> Result<(), Error> {
+        match target {
+            Target::Pid(pid) => {
+                ProcessHandle::open(*pid, PROCESS_SET_INFORMATION)?.set_affinity(mask)
+            }
+            Target::Id(id) => self.launch(id)?.job.set_affinity(mask),
+        }
+    }
+
+    /// Every launch the bridge has started, by id, including the most recent
+    /// finished ones.
+    pub fn launches(&self) -> Vec<(String, Arc<Launch>)> {
+        self.launches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(id, launch)| (id.clone(), launch.clone()))
+            .collect()
+    }
+
+    /// Launches of a previous bridge instance that were still running when
+    /// this one started, until their processes have exited.
+    pub fn recovered(&self) -> Vec<(String, Arc<Recovered>)> {
+        self.recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(id, recovered)| (id.clone(), recovered.clone()))
+            .collect()
+    }
+
+    /// The recovered launch `id`, failing with `ERROR_NOT_FOUND` for ids
+    /// that were not recovered or whose processes have all exited since.
+    pub fn recovered_launch(&self, id: &str) -> Result<Arc<Recovered>, Error> {
+        self.recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::from_hresult(HRESULT::from_win32(ERROR_NOT_FOUND.0)))
+    }
+
+    /// Maps the pid of every process that belongs to a launch, recovered ones
+    /// included, to that launch's id.
+    pub fn launch_ids(&self) -> HashMap<u32, String> {
+        let mut ids = HashMap::new();
+        let mut add = |id: &str, job: &Job| match job.process_ids() {
+            Ok(pids) => ids.extend(pids.into_iter().map(|pid| (pid, id.to_string()))),
+            Err(error) => tracing::warn!("Failed to list processes of launch {id}: {error}"),
+        };
+        for (id, launch) in self.launches() {
+            add(&id, &launch.job);
+        }
+        for (id, recovered) in self.recovered() {
+            add(&id, &recovered.job);
+        }
+        ids
+    }
+
+    /// The launch started with `id`, failing with `ERROR_NOT_FOUND` for ids
+    /// the bridge never launched.
+    pub fn launch(&self, id: &str) -> Result<Arc<Launch>, Error> {
+        self.launches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::from_hresult(HRESULT::from_win32(ERROR_NOT_FOUND.0)))
+    }
+
+    /// The hub reporting lifecycle events of every launch.
+    pub fn events(&self) -> Result<Arc<EventHub>, Error> {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(hub) = &*events {
+            return Ok(hub.clone());
+        }
+        let hub = EventHub::new(self.table.clone())?;
+        *events = Some(hub.clone());
+        Ok(hub)
+    }
+
+    /// Reports the processes of `job` as events of launch `id`. Losing the
+    /// events does not stop the launch, so failures are only logged.
+    fn watch_events(&self, job: &Job, id: &str) {
+        if let Err(error) = self.events().and_then(|hub| hub.watch(job, id)) {
+            tracing::warn!("Failed to watch events of launch {id}: {error}");
+        }
+    }
+
+    fn spawn(
+        &self,
+        reservation: Reservation<'_>,
+        request: winebridge::LaunchProcessRequest,
+        stdio: Stdio,
+    ) -> Result<Arc<Launch>, Error> {
+        let (job, joined) = self.open_job(&request)?;
+        self.watch_events(&job, &request.id);
+        let launch = create(job, &request, stdio)?;
+        start_watchdog(&launch, &request);
+        let record = self.register(reservation, &request, launch.clone());
+        self.watch_exit(&launch, &request.id, joined, record);
+        Ok(launch)
+    }
+
+    /// Waits on a dedicated thread for the job of `launch` to empty, which
+    /// ends its run right then rather than whenever a client next waits on
+    /// it, then records the run into the history and drops `record` from
+    /// the launch table. A launch that joined a running job is left out of
+    /// the history, as the run of the launch that created the job already
+    /// covers it.
+    fn watch_exit(
+        &self,
+        launch: &Arc<Launch>,
+        id: &str,
+        joined: bool,
+        record: Option<LaunchRecord>,
+    ) {
+        let launch = launch.clone();
+        let id = id.to_string();
+        let history = (!joined).then(|| self.history.clone());
+        let table = self.table.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = launch.wait(None) {
+                tracing::warn!("Failed to wait for launch {id}: {error}");
+                return;
+            }
+            if let Some(history) = history {
+                accounting::record(&launch, &id, &history);
+            }
+            if let Some(record) = record
+                && let Err(error) = table.remove_record(&id, &record.job)
+            {
+                tracing::warn!("Failed to drop launch {id} from the launch table: {error}");
+            }
+        });
+    }
+
+    /// Recorded runs of launch `id`, oldest first, followed by the one still
+    /// going if the launch is running.
+    pub fn accounting(&self, id: &str) -> Result<Vec<Run>, Error> {
+        let mut runs = self.history.runs(id)?;
+        if let Ok(launch) = self.launch(id)
+            && launch.job.active_processes()? > 0
+        {
+            runs.push(Run::of(id.to_string(), &launch)?);
         }
+        Ok(runs)
+    }
 
-        Ok(process_info.0.dwProcessId)
+    /// Remembers `launch` under its id, and in the launch table too unless
+    /// it dies with the bridge anyway, returning the record saved there.
+    fn register(
+        &self,
+        reservation: Reservation<'_>,
+        request: &winebridge::LaunchProcessRequest,
+        launch: Arc<Launch>,
+    ) -> Option<LaunchRecord> {
+        let record = if dies_with_bridge(request) {
+            None
+        } else {
+            let saved = launch.record().map_err(io::Error::from).and_then(|record| {
+                self.table.insert(request.id.clone(), record.clone())?;
+                Ok(record)
+            });
+            match saved {
+                Ok(record) => Some(record),
+                Err(error) => {
+                    tracing::warn!(
+                        "Failed to save launch {} to the launch table: {error}",
+                        request.id
+                    );
+                    None
+                }
+            }
+        };
+        self.recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&request.id);
+        let mut launches = self.launches.lock().unwrap_or_else(PoisonError::into_inner);
+        launches.insert(request.id.clone(), launch);
+        // Released only now, so the id is never free while the launch runs.
+        drop(reservation);
+        evict_finished(&mut launches);
+        record
     }
 
+    /// Terminates every process of launch `id`, failing with
+    /// `ERROR_NOT_FOUND` for ids that are neither launched nor recovered. A
+    /// killed launch leaves the launch table, and is forgotten if it was
+    /// recovered.
     pub fn kill(&self, id: &str) -> Result<(), Error> {
-        let job = Job::open(id)?;
-        unsafe { TerminateJobObject(job.0, 0) }
+        match self.launch(id) {
+            Ok(launch) => launch.job.terminate(0)?,
+            Err(_) => self.recovered_launch(id)?.job.terminate(0)?,
+        }
+        self.forget_terminated(id);
+        Ok(())
+    }
+
+    /// Drops launch `id`, whose processes have all been terminated, from
+    /// the launch table, and forgets it if it was recovered.
+    fn forget_terminated(&self, id: &str) {
+        self.recovered
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+        if let Err(error) = self.table.remove(id) {
+            tracing::warn!("Failed to drop launch {id} from the launch table: {error}");
+        }
+    }
+
+    /// Asks every process of launch `id` to exit, by closing its visible,
+    /// unowned top-level windows and sending Ctrl+C to its console, then
+    /// Ctrl+Break halfway through the grace period, and terminates the job once
+    /// `grace_period` has passed with any of them still running. Fails with
+    /// `ERROR_NOT_FOUND` for ids that are neither launched nor recovered.
+    /// Like [`Self::kill`], a stopped launch leaves the launch table.
+    pub fn stop(&self, id: &str, grace_period: Duration) -> Result<StopReport, Error> {
+        let report = match self.launch(id) {
+            Ok(launch) => stop_job(&launch.job, grace_period)?,
+            Err(_) => stop_job(&self.recovered_launch(id)?.job, grace_period)?,
+        };
+        self.forget_terminated(id);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processes::testing;
+    use windows::Win32::Foundation::{ERROR_BROKEN_PIPE, ERROR_OPERATION_ABORTED};
+
+    fn is_busy<T>(result: Result<T, Error>) -> bool {
+        result.err().map(|error| error.code()) == Some(HRESULT::from_win32(ERROR_ALREADY_EXISTS.0))
+    }
+
+    #[test]
+    fn refuses_ids_that_are_starting_or_running_until_they_exit() {
+        let processes = testing::manager("reserve");
+        let request = testing::pause_launch(&testing::unique_name("reserve"));
+
+        let reservation = processes.reserve(&request).unwrap();
+        assert!(is_busy(processes.reserve(&request)));
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+        assert!(is_busy(processes.reserve(&request)));
+        let joining = winebridge::LaunchProcessRequest {
+            join_existing: true,
+            ..request.clone()
+        };
+        assert!(processes.reserve(&joining).is_ok());
+
+        launch.close_stdin();
+        launch.wait(Some(Duration::from_secs(10))).unwrap();
+        assert!(processes.reserve(&request).is_ok());
+    }
+
+    #[test]
+    fn runs_every_launch_of_an_id_in_a_fresh_job() {
+        let processes = testing::manager("fresh-job");
+        let id = testing::unique_name("fresh-job");
+        let launch = |command_line: &str, limits| {
+            let request = winebridge::LaunchProcessRequest {
+                id: id.clone(),
+                executable: testing::system_program("cmd"),
+                command_line: Some(command_line.to_string()),
+                limits,
+                lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+                ..Default::default()
+            };
+            let reservation = processes.reserve(&request).unwrap();
+            let launch = processes.execute(reservation, request).unwrap().unwrap();
+            let exit = launch.wait(Some(Duration::from_secs(10))).unwrap();
+            (launch, exit.exit_code)
+        };
+
+        let (first, _) = launch(
+            "/c exit 0",
+            Some(winebridge::ProcessLimits {
+                active_processes: Some(1),
+                ..Default::default()
+            }),
+        );
+        // The nested cmd would be refused under the first run's limit.
+        let (second, exit_code) = launch("/c cmd /c exit 3", None);
+        assert_eq!(exit_code, 3);
+        assert_ne!(first.job.name(), second.job.name());
+    }
+
+    #[test]
+    fn stops_a_launch_by_closing_its_window() {
+        let processes = testing::manager("stop");
+        let request = testing::notepad_launch(&testing::unique_name("stop"));
+        let reservation = processes.reserve(&request).unwrap();
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+        let pid = launch.process.pid();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !WindowManager
+            .list()
+            .unwrap()
+            .iter()
+            .any(|window| window.pid == pid && window.is_visible())
+        {
+            assert!(Instant::now() < deadline, "notepad never showed a window");
+            std::thread::sleep(JOB_POLL_INTERVAL);
+        }
+
+        let report = processes
+            .stop(&request.id, Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(report.exited, [pid]);
+        assert!(report.killed.is_empty());
+        assert_eq!(launch.wait(Some(Duration::ZERO)).unwrap().exit_code, 0);
+    }
+
+    #[test]
+    fn recovers_every_process_of_a_detached_launch_after_a_restart() {
+        let directory = testing::state_directory("restart");
+        let processes = ProcessManager::in_directory(&directory);
+        // The outer cmd waits for the nested one, which waits for stdin.
+        let request = winebridge::LaunchProcessRequest {
+            command_line: Some("/c cmd /c pause".to_string()),
+            arguments: Vec::new(),
+            lifecycle: winebridge::LaunchLifecycle::Unspecified as i32,
+            ..testing::pause_launch(&testing::unique_name("restart"))
+        };
+        let reservation = processes.reserve(&request).unwrap();
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+        let table = LaunchTable::new(directory.join(crate::processes::launch_table::TABLE_FILE));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut pids = loop {
+            let pids = launch.job.process_ids().unwrap();
+            let recorded = table
+                .load()
+                .unwrap()
+                .remove(&request.id)
+                .map_or(0, |record| {
+                    record
+                        .members
+                        .iter()
+                        .filter(|member| pids.contains(&member.pid))
+                        .count()
+                });
+            if pids.len() == 2 && recorded == 2 {
+                break pids;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "the nested cmd was never recorded"
+            );
+            std::thread::sleep(JOB_POLL_INTERVAL);
+        };
+        drop(processes);
+
+        let processes = ProcessManager::in_directory(&directory);
+        let recovered = processes.recovered_launch(&request.id).unwrap();
+        let mut recovered_pids = recovered.job.process_ids().unwrap();
+        pids.sort_unstable();
+        recovered_pids.sort_unstable();
+        assert_eq!(recovered_pids, pids);
+        assert_eq!(recovered.record.primary_pid, launch.process.pid());
+
+        processes.kill(&request.id).unwrap();
+        launch.wait(Some(Duration::from_secs(10))).unwrap();
+        assert!(recovered.job.process_ids().unwrap().is_empty());
+        assert!(!table.load().unwrap().contains_key(&request.id));
+    }
+
+    #[test]
+    fn suspends_and_resumes_every_thread_of_a_launch() {
+        let processes = testing::manager("suspend");
+        // Keeps a CPU busy for a while, then exits normally.
+        let request = winebridge::LaunchProcessRequest {
+            id: testing::unique_name("suspend"),
+            executable: testing::system_program("cmd"),
+            command_line: Some("/c for /l %i in (1,1,100000) do @rem".to_string()),
+            lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+            ..Default::default()
+        };
+        let reservation = processes.reserve(&request).unwrap();
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+
+        processes.suspend(&request.id).unwrap();
+        assert!(launch.is_suspended());
+        let cpu_time = launch.job.cpu_time().unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert_eq!(launch.job.cpu_time().unwrap(), cpu_time);
+        assert!(launch.job.active_processes().unwrap() > 0);
+
+        processes.resume(&request.id).unwrap();
+        assert!(!launch.is_suspended());
+        let exit = processes
+            .wait(&request.id, Some(Duration::from_secs(120)))
+            .unwrap();
+        assert_eq!(exit.exit_code, 0);
+    }
+
+    #[test]
+    fn feeds_and_closes_the_stdin_of_a_launch() {
+        let processes = testing::manager("stdin");
+        // Runs the commands it reads from stdin until that ends.
+        let request = winebridge::LaunchProcessRequest {
+            id: testing::unique_name("stdin"),
+            executable: testing::system_program("cmd"),
+            redirect_stdin: true,
+            lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+            ..Default::default()
+        };
+        let (sender, mut receiver) = mpsc::channel(64);
+        let reservation = processes.reserve(&request).unwrap();
+        processes
+            .execute_with_output(reservation, request.clone(), sender)
+            .unwrap();
+
+        processes
+            .write_stdin(&request.id, b"echo written-to-stdin\r\n")
+            .unwrap();
+        processes.close_stdin(&request.id).unwrap();
+        let exit = processes
+            .wait(&request.id, Some(Duration::from_secs(10)))
+            .unwrap();
+        assert_eq!(exit.exit_code, 0);
+
+        let mut stdout = Vec::new();
+        while let Some(output) = receiver.blocking_recv() {
+            if let Some(Event::Chunk(chunk)) = output.event
+                && chunk.stream() == winebridge::OutputStream::Stdout
+            {
+                stdout.extend(chunk.data);
+            }
+        }
+        let stdout = String::from_utf8_lossy(&stdout);
+        assert!(stdout.lines().any(|line| line == "written-to-stdin"));
+        assert_eq!(
+            processes
+                .write_stdin(&request.id, b"exit\r\n")
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_BROKEN_PIPE.0))
+        );
+    }
+
+    #[test]
+    fn closing_stdin_cancels_a_write_the_launch_never_reads() {
+        let processes = testing::manager("stdin-cancel");
+        // Notepad never reads its stdin, so the pipe fills up.
+        let request = winebridge::LaunchProcessRequest {
+            redirect_stdin: true,
+            ..testing::notepad_launch(&testing::unique_name("stdin-cancel"))
+        };
+        let reservation = processes.reserve(&request).unwrap();
+        let launch = processes
+            .execute(reservation, request.clone())
+            .unwrap()
+            .unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let writer = launch.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(writer.write_stdin(&vec![b'x'; 16 * 1024 * 1024]));
+        });
+        std::thread::sleep(Duration::from_millis(500));
+        assert!(receiver.try_recv().is_err(), "the write did not block");
+
+        processes.close_stdin(&request.id).unwrap();
+        let written = receiver.recv_timeout(Duration::from_secs(10));
+        processes.kill(&request.id).unwrap();
+        assert_eq!(
+            written
+                .expect("closing stdin did not cancel the write")
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_OPERATION_ABORTED.0))
+        );
     }
 }
@@ -1,2 +1,164 @@
+pub mod accounting;
+pub mod command_line;
+pub mod console;
+pub mod crash;
+pub mod environment;
+pub mod events;
+pub mod job;
+pub mod launch;
+pub mod launch_table;
 pub mod manager;
+pub mod module;
+pub mod monitor;
+pub mod pipe;
 pub mod process;
+pub mod pseudo_console;
+pub mod session;
+pub mod shell;
+pub mod stack;
+pub mod state;
+#[cfg(test)]
+pub mod testing;
+pub mod thread;
+pub mod watchdog;
+
+use std::{ffi::OsStr, os::windows::ffi::OsStrExt};
+
+use next_proto::winebridge;
+use windows::Win32::System::Threading::{
+    ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+    IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, PROCESS_CREATION_FLAGS,
+};
+
+/// `s` as a NUL-terminated UTF-16 string for the wide Win32 APIs.
+pub(crate) fn to_wide_string(s: impl AsRef<OsStr>) -> Vec<u16> {
+    s.as_ref().encode_wide().chain(Some(0)).collect()
+}
+
+/// The Win32 priority class for `priority`, or `None` when it is unspecified.
+pub fn priority_class(priority: winebridge::PriorityClass) -> Option<PROCESS_CREATION_FLAGS> {
+    match priority {
+        winebridge::PriorityClass::Unspecified => None,
+        winebridge::PriorityClass::Idle => Some(IDLE_PRIORITY_CLASS),
+        winebridge::PriorityClass::BelowNormal => Some(BELOW_NORMAL_PRIORITY_CLASS),
+        winebridge::PriorityClass::Normal => Some(NORMAL_PRIORITY_CLASS),
+        winebridge::PriorityClass::AboveNormal => Some(ABOVE_NORMAL_PRIORITY_CLASS),
+        winebridge::PriorityClass::High => Some(HIGH_PRIORITY_CLASS),
+    }
+}
+
+pub fn stats_to_proto(stats: &process::ProcessStats) -> winebridge::ProcessStats {
+    winebridge::ProcessStats {
+        working_set_bytes: stats.working_set,
+        private_bytes: stats.private_bytes,
+        page_faults: stats.page_faults,
+        handle_count: stats.handle_count,
+        kernel_time_ms: stats.kernel_time.as_millis() as u64,
+        user_time_ms: stats.user_time.as_millis() as u64,
+    }
+}
+
+pub fn watchdog_to_proto(watchdog: Option<watchdog::Watchdog>) -> winebridge::Watchdog {
+    match watchdog {
+        None => winebridge::Watchdog::Unspecified,
+        Some(watchdog::Watchdog::Timeout) => winebridge::Watchdog::Timeout,
+        Some(watchdog::Watchdog::Idle) => winebridge::Watchdog::Idle,
+    }
+}
+
+/// Describes `launch` with its current member processes, which are left out
+/// when the job cannot be queried.
+pub fn launch_to_proto(id: String, launch: &launch::Launch) -> winebridge::LaunchInfo {
+    launch_info(
+        id,
+        &launch.job,
+        &launch.command,
+        launch.process.pid(),
+        launch.started_at,
+        false,
+    )
+}
+
+pub fn recovered_to_proto(
+    id: String,
+    recovered: &launch_table::Recovered,
+) -> winebridge::LaunchInfo {
+    let record = &recovered.record;
+    launch_info(
+        id,
+        &recovered.job,
+        &record.command,
+        record.primary_pid,
+        record.started_at,
+        true,
+    )
+}
+
+fn launch_info(
+    id: String,
+    job: &job::Job,
+    command: &launch::Command,
+    primary_pid: u32,
+    started_at: std::time::SystemTime,
+    recovered: bool,
+) -> winebridge::LaunchInfo {
+    let pids = match job.process_ids() {
+        Ok(pids) => pids,
+        Err(error) => {
+            tracing::warn!("Failed to list processes of launch {id}: {error}");
+            Vec::new()
+        }
+    };
+    winebridge::LaunchInfo {
+        executable: command.executable.clone(),
+        command_line: command.command_line.clone(),
+        primary_pid,
+        started_unix_ms: started_at
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64),
+        running: !pids.is_empty(),
+        pids,
+        recovered,
+        id,
+    }
+}
+
+pub fn run_to_proto(run: &accounting::Run) -> winebridge::LaunchRun {
+    winebridge::LaunchRun {
+        started_unix_ms: run
+            .started_at
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64),
+        wall_time_ms: run.wall_time.as_millis() as u64,
+        user_time_ms: run.usage.user_time.as_millis() as u64,
+        kernel_time_ms: run.usage.kernel_time.as_millis() as u64,
+        peak_memory_bytes: run.usage.peak_memory,
+        read_bytes: run.usage.read_bytes,
+        write_bytes: run.usage.write_bytes,
+        other_bytes: run.usage.other_bytes,
+    }
+}
+
+pub fn stack_to_proto(stack: &stack::ThreadStack) -> winebridge::ThreadStack {
+    let (frames, error) = match &stack.frames {
+        Ok(frames) => (frames.iter().map(frame_to_proto).collect(), None),
+        Err(error) => (Vec::new(), Some(error.message())),
+    };
+    winebridge::ThreadStack {
+        tid: stack.tid,
+        frames,
+        error,
+    }
+}
+
+fn frame_to_proto(frame: &stack::Frame) -> winebridge::StackFrame {
+    let (module, module_offset) = frame.module.clone().unzip();
+    let (symbol, symbol_offset) = frame.symbol.clone().unzip();
+    winebridge::StackFrame {
+        address: frame.address,
+        module,
+        module_offset,
+        symbol,
+        symbol_offset,
+    }
+}
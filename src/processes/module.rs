@@ -0,0 +1,105 @@
+use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, ERROR_BAD_LENGTH, HANDLE},
+        System::Diagnostics::ToolHelp::{
+            CreateToolhelp32Snapshot, MODULEENTRY32W, Module32FirstW, Module32NextW,
+            TH32CS_SNAPMODULE, TH32CS_SNAPMODULE32,
+        },
+    },
+    core::{Error, HRESULT},
+};
+
+/// How many times a module snapshot is retried while the process is still
+/// loading or unloading modules.
+const SNAPSHOT_ATTEMPTS: usize = 8;
+
+fn from_wide_nul(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    OsString::from_wide(&wide[..len])
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct Module(MODULEENTRY32W);
+
+impl Module {
+    pub fn name(&self) -> String {
+        from_wide_nul(&self.0.szModule)
+    }
+
+    pub fn path(&self) -> String {
+        from_wide_nul(&self.0.szExePath)
+    }
+
+    pub fn base_address(&self) -> u64 {
+        self.0.modBaseAddr as u64
+    }
+
+    pub fn size(&self) -> u32 {
+        self.0.modBaseSize
+    }
+}
+
+/// Every module loaded into one process, 32-bit ones of WOW64 processes
+/// included, as of when the snapshot was taken.
+pub struct ModuleSnapshot {
+    handle: HANDLE,
+    initialized: bool,
+}
+
+impl ModuleSnapshot {
+    pub fn new(pid: u32) -> Result<Self, Error> {
+        let mut attempt = 1;
+        loop {
+            // The snapshot fails with ERROR_BAD_LENGTH while the process's
+            // module list is changing, so it is simply taken again.
+            match unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid) }
+            {
+                Ok(handle) => {
+                    return Ok(Self {
+                        handle,
+                        initialized: false,
+                    });
+                }
+                Err(error)
+                    if error.code() == HRESULT::from_win32(ERROR_BAD_LENGTH.0)
+                        && attempt < SNAPSHOT_ATTEMPTS =>
+                {
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Iterator for ModuleSnapshot {
+    type Item = Module;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = MODULEENTRY32W {
+            dwSize: std::mem::size_of::<MODULEENTRY32W>() as u32,
+            ..Default::default()
+        };
+
+        if !self.initialized {
+            unsafe { Module32FirstW(self.handle, &mut entry) }.ok()?;
+            self.initialized = true;
+        } else {
+            unsafe { Module32NextW(self.handle, &mut entry) }.ok()?;
+        }
+
+        Some(Module(entry))
+    }
+}
+
+impl Drop for ModuleSnapshot {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use super::process::{ProcessHandle, ProcessSnapshot};
+use next_proto::winebridge;
+use tokio::sync::mpsc;
+
+/// Share of the machine's total CPU capacity a process used between two
+/// samples of its accumulated CPU time, in percent.
+pub fn cpu_percent(previous: Duration, current: Duration, elapsed: Duration, cpus: usize) -> f64 {
+    let capacity = elapsed.as_secs_f64() * cpus.max(1) as f64;
+    if capacity == 0.0 {
+        return 0.0;
+    }
+    (current.saturating_sub(previous).as_secs_f64() / capacity * 100.0).min(100.0)
+}
+
+/// Sends a sample of every running process to `samples` each `interval`,
+/// until the receiver goes away.
+///
+/// The first sample carries no CPU percentages, since they are measured
+/// against the sample before.
+pub fn run(interval: Duration, samples: mpsc::Sender<winebridge::ProcessSample>) {
+    let cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut previous: HashMap<u32, (Instant, Duration)> = HashMap::new();
+
+    loop {
+        let snapshot = match ProcessSnapshot::new() {
+            Ok(snapshot) => snapshot,
+            Err(error) => {
+                tracing::warn!("Failed to snapshot processes for monitoring: {error}");
+                return;
+            }
+        };
+
+        let mut current = HashMap::new();
+        let processes = snapshot
+            .map(|process| {
+                let stats = ProcessHandle::query(process.pid())
+                    .and_then(|handle| handle.stats())
+                    .ok();
+                let cpu_percent = stats.as_ref().and_then(|stats| {
+                    let now = Instant::now();
+                    current.insert(process.pid(), (now, stats.cpu_time()));
+                    let (then, before) = previous.get(&process.pid())?;
+                    Some(cpu_percent(*before, stats.cpu_time(), now - *then, cpus))
+                });
+
+                winebridge::ProcessUsage {
+                    pid: process.pid(),
+                    name: process.name(),
+                    stats: stats.as_ref().map(super::stats_to_proto),
+                    cpu_percent,
+                }
+            })
+            .collect();
+        previous = current;
+
+        if samples
+            .blocking_send(winebridge::ProcessSample { processes })
+            .is_err()
+        {
+            return;
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_percent_is_relative_to_all_cpus() {
+        let second = Duration::from_secs(1);
+        assert_eq!(cpu_percent(Duration::ZERO, second, second, 1), 100.0);
+        assert_eq!(cpu_percent(Duration::ZERO, second, second, 4), 25.0);
+        assert_eq!(cpu_percent(second, Duration::ZERO, second, 1), 0.0);
+        assert_eq!(cpu_percent(Duration::ZERO, second, Duration::ZERO, 1), 0.0);
+    }
+}
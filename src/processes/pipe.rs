@@ -0,0 +1,123 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use windows::{
+    Win32::{
+        Foundation::{
+            CloseHandle, ERROR_BROKEN_PIPE, HANDLE, HANDLE_FLAG_INHERIT, HANDLE_FLAGS,
+            SetHandleInformation,
+        },
+        Storage::FileSystem::{ReadFile, WriteFile},
+        System::{IO::CancelIoEx, Pipes::CreatePipe},
+    },
+    core::{Error, HRESULT},
+};
+
+/// One end of an anonymous pipe, closed on drop.
+pub struct PipeHandle(HANDLE);
+
+// SAFETY: a pipe handle is a plain kernel handle; ReadFile/WriteFile on it are
+// safe to issue from any thread.
+unsafe impl Send for PipeHandle {}
+unsafe impl Sync for PipeHandle {}
+
+impl PipeHandle {
+    pub fn raw(&self) -> HANDLE {
+        self.0
+    }
+
+    /// Marks the handle inheritable (or not) by processes created with
+    /// `bInheritHandles` set.
+    pub fn set_inheritable(&self, inheritable: bool) -> Result<(), Error> {
+        let flags = if inheritable {
+            HANDLE_FLAG_INHERIT
+        } else {
+            HANDLE_FLAGS(0)
+        };
+        unsafe { SetHandleInformation(self.0, HANDLE_FLAG_INHERIT.0, flags) }
+    }
+
+    /// Reads up to `buffer.len()` bytes, returning `Ok(0)` once every writer
+    /// has closed its end.
+    pub fn read(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut read = 0u32;
+        match unsafe { ReadFile(self.0, Some(buffer), Some(&mut read), None) } {
+            Ok(()) => Ok(read as usize),
+            Err(error) if error.code() == HRESULT::from_win32(ERROR_BROKEN_PIPE.0) => Ok(0),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn write_all(&self, mut data: &[u8]) -> Result<(), Error> {
+        while !data.is_empty() {
+            let mut written = 0u32;
+            unsafe { WriteFile(self.0, Some(data), Some(&mut written), None) }?;
+            data = &data[written as usize..];
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PipeHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Our end of a pipe feeding a child's input, written to by request threads
+/// and closed by whichever asks first.
+///
+/// Writes run outside the lock, so a write blocked on a full pipe never
+/// holds up [`Self::close`], which cancels it instead.
+pub struct PipeWriter(Mutex<Option<Arc<PipeHandle>>>);
+
+impl PipeWriter {
+    pub fn new(pipe: Option<PipeHandle>) -> Self {
+        Self(Mutex::new(pipe.map(Arc::new)))
+    }
+
+    /// Writes all of `data`, failing with `ERROR_BROKEN_PIPE` when there is
+    /// no pipe or it is already closed, and with `ERROR_OPERATION_ABORTED`
+    /// when it is closed mid-write.
+    pub fn write_all(&self, data: &[u8]) -> Result<(), Error> {
+        let pipe = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or_else(|| Error::from_hresult(HRESULT::from_win32(ERROR_BROKEN_PIPE.0)))?;
+        pipe.write_all(data)
+    }
+
+    /// Closes the pipe once no write is using it any more, cancelling the
+    /// writes in progress.
+    pub fn close(&self) {
+        let pipe = self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(pipe) = pipe {
+            // Nothing to cancel is the usual case, and not an error.
+            unsafe {
+                let _ = CancelIoEx(pipe.raw(), None);
+            }
+        }
+    }
+}
+
+/// An anonymous pipe. Both ends start out non-inheritable; the end handed to a
+/// child is made inheritable only for the duration of `CreateProcessW`.
+pub struct Pipe {
+    pub read: PipeHandle,
+    pub write: PipeHandle,
+}
+
+impl Pipe {
+    pub fn new() -> Result<Self, Error> {
+        let mut read = HANDLE::default();
+        let mut write = HANDLE::default();
+        unsafe { CreatePipe(&mut read, &mut write, None, 0) }?;
+        Ok(Self {
+            read: PipeHandle(read),
+            write: PipeHandle(write),
+        })
+    }
+}
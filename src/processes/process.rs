@@ -1,22 +1,96 @@
-use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+use std::{
+    ffi::OsString,
+    os::windows::ffi::OsStringExt,
+    time::{Duration, SystemTime},
+};
 
 use windows::{
     Win32::{
-        Foundation::{CloseHandle, HANDLE},
+        Foundation::{CloseHandle, ERROR_TIMEOUT, FILETIME, HANDLE, WAIT_FAILED, WAIT_TIMEOUT},
         System::{
             Diagnostics::ToolHelp::{
                 CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
                 TH32CS_SNAPPROCESS,
             },
-            Threading::PROCESS_INFORMATION,
+            ProcessStatus::{
+                GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS, PROCESS_MEMORY_COUNTERS_EX,
+            },
+            Threading::{
+                GetExitCodeProcess, GetProcessHandleCount, GetProcessTimes, INFINITE,
+                IsWow64Process, OpenProcess, PROCESS_ACCESS_RIGHTS, PROCESS_CREATION_FLAGS,
+                PROCESS_INFORMATION, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+                QueryFullProcessImageNameW, SetPriorityClass, SetProcessAffinityMask,
+                WaitForSingleObject,
+            },
         },
     },
-    core::Error,
+    core::{BOOL, Error, HRESULT, PWSTR},
 };
 
+/// Longest image path `QueryFullProcessImageNameW` is asked for, the maximum
+/// length of an extended-length Windows path.
+const MAX_IMAGE_PATH: usize = 32 * 1024;
+
+/// Converts a `FILETIME` holding an interval (rather than a point in time)
+/// into a [`Duration`].
+pub fn filetime_duration(time: FILETIME) -> Duration {
+    let ticks = (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+    Duration::from_nanos(ticks.saturating_mul(100))
+}
+
+/// Time between the `FILETIME` epoch, 1601-01-01, and the Unix epoch.
+const FILETIME_UNIX_OFFSET: Duration = Duration::from_secs(11_644_473_600);
+
+/// Converts a `FILETIME` holding a point in time into a [`SystemTime`].
+pub fn filetime_system_time(time: FILETIME) -> SystemTime {
+    let since_1601 = filetime_duration(time);
+    match since_1601.checked_sub(FILETIME_UNIX_OFFSET) {
+        Some(since_unix) => SystemTime::UNIX_EPOCH + since_unix,
+        None => SystemTime::UNIX_EPOCH - (FILETIME_UNIX_OFFSET - since_1601),
+    }
+}
+
+/// Resource usage of one process at the moment it was queried.
+#[derive(Debug, Clone)]
+pub struct ProcessStats {
+    pub working_set: u64,
+    pub private_bytes: u64,
+    pub page_faults: u32,
+    pub handle_count: u32,
+    pub kernel_time: Duration,
+    pub user_time: Duration,
+}
+
+impl ProcessStats {
+    pub fn cpu_time(&self) -> Duration {
+        self.kernel_time + self.user_time
+    }
+}
+
 #[derive(Default)]
 pub struct ProcessInfo(pub PROCESS_INFORMATION);
 
+// SAFETY: the process and thread handles are owned exclusively by this value
+// and may be waited on or closed from any thread.
+unsafe impl Send for ProcessInfo {}
+unsafe impl Sync for ProcessInfo {}
+
+impl ProcessInfo {
+    pub fn pid(&self) -> u32 {
+        self.0.dwProcessId
+    }
+
+    /// Blocks until the process exits, or `timeout` elapses, and returns its
+    /// exit code.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<u32, Error> {
+        wait(self.0.hProcess, timeout)
+    }
+
+    pub fn created(&self) -> Result<SystemTime, Error> {
+        created(self.0.hProcess)
+    }
+}
+
 impl Drop for ProcessInfo {
     fn drop(&mut self) {
         unsafe {
@@ -26,6 +100,133 @@ impl Drop for ProcessInfo {
     }
 }
 
+fn wait(process: HANDLE, timeout: Option<Duration>) -> Result<u32, Error> {
+    let milliseconds = timeout.map_or(INFINITE, |timeout| {
+        timeout.as_millis().min(u128::from(INFINITE - 1)) as u32
+    });
+    match unsafe { WaitForSingleObject(process, milliseconds) } {
+        WAIT_FAILED => return Err(Error::from_thread()),
+        WAIT_TIMEOUT => return Err(Error::from_hresult(HRESULT::from_win32(ERROR_TIMEOUT.0))),
+        _ => {}
+    }
+    let mut exit_code = 0;
+    unsafe { GetExitCodeProcess(process, &mut exit_code) }?;
+    Ok(exit_code)
+}
+
+fn created(process: HANDLE) -> Result<SystemTime, Error> {
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    unsafe { GetProcessTimes(process, &mut creation, &mut exit, &mut kernel, &mut user) }?;
+    Ok(filetime_system_time(creation))
+}
+
+/// A handle to a running process opened by pid, closed on drop.
+pub struct ProcessHandle(HANDLE);
+
+impl ProcessHandle {
+    pub fn open(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> Result<Self, Error> {
+        Ok(Self(unsafe { OpenProcess(access, false, pid) }?))
+    }
+
+    /// Opens a process for every query below.
+    pub fn query(pid: u32) -> Result<Self, Error> {
+        Self::open(pid, PROCESS_QUERY_LIMITED_INFORMATION)
+    }
+
+    pub fn raw(&self) -> HANDLE {
+        self.0
+    }
+
+    /// Full Win32 path of the process's executable image.
+    pub fn image_path(&self) -> Result<String, Error> {
+        let mut buffer = vec![0u16; MAX_IMAGE_PATH];
+        let mut len = buffer.len() as u32;
+        unsafe {
+            QueryFullProcessImageNameW(
+                self.0,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut len,
+            )
+        }?;
+
+        Ok(OsString::from_wide(&buffer[..len as usize])
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// When the process was created, which tells it apart from a later
+    /// process reusing its pid.
+    pub fn created(&self) -> Result<SystemTime, Error> {
+        created(self.0)
+    }
+
+    /// Like [`ProcessInfo::wait`]. The handle needs `PROCESS_SYNCHRONIZE`.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<u32, Error> {
+        wait(self.0, timeout)
+    }
+
+    /// Whether this is a 32-bit process running under WOW64.
+    pub fn is_wow64(&self) -> Result<bool, Error> {
+        let mut wow64 = BOOL::default();
+        unsafe { IsWow64Process(self.0, &mut wow64) }?;
+        Ok(wow64.as_bool())
+    }
+
+    pub fn set_priority_class(&self, class: PROCESS_CREATION_FLAGS) -> Result<(), Error> {
+        unsafe { SetPriorityClass(self.0, class) }
+    }
+
+    /// Restricts the process to the CPUs set in `mask`, which must be a subset
+    /// of the CPUs the system has.
+    pub fn set_affinity(&self, mask: u64) -> Result<(), Error> {
+        unsafe { SetProcessAffinityMask(self.0, mask as usize) }
+    }
+
+    pub fn stats(&self) -> Result<ProcessStats, Error> {
+        let mut memory = PROCESS_MEMORY_COUNTERS_EX {
+            cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS_EX>() as u32,
+            ..Default::default()
+        };
+        unsafe {
+            GetProcessMemoryInfo(
+                self.0,
+                &mut memory as *mut _ as *mut PROCESS_MEMORY_COUNTERS,
+                memory.cb,
+            )
+        }?;
+
+        let mut handle_count = 0;
+        unsafe { GetProcessHandleCount(self.0, &mut handle_count) }?;
+
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        unsafe { GetProcessTimes(self.0, &mut creation, &mut exit, &mut kernel, &mut user) }?;
+
+        Ok(ProcessStats {
+            working_set: memory.WorkingSetSize as u64,
+            private_bytes: memory.PrivateUsage as u64,
+            page_faults: memory.PageFaultCount,
+            handle_count,
+            kernel_time: filetime_duration(kernel),
+            user_time: filetime_duration(user),
+        })
+    }
+}
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Process(PROCESSENTRY32W);
 
@@ -50,6 +251,10 @@ impl Process {
     pub fn thread_count(&self) -> u32 {
         self.0.cntThreads
     }
+
+    pub fn parent_pid(&self) -> u32 {
+        self.0.th32ParentProcessID
+    }
 }
 
 pub struct ProcessSnapshot {
@@ -93,3 +298,31 @@ impl Drop for ProcessSnapshot {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filetime(ticks: u64) -> FILETIME {
+        FILETIME {
+            dwLowDateTime: ticks as u32,
+            dwHighDateTime: (ticks >> 32) as u32,
+        }
+    }
+
+    #[test]
+    fn converts_filetimes() {
+        assert_eq!(
+            filetime_duration(filetime(0x1_0000_0000)),
+            Duration::from_nanos(0x1_0000_0000 * 100)
+        );
+        assert_eq!(
+            filetime_system_time(filetime(116_444_736_000_000_000)),
+            SystemTime::UNIX_EPOCH
+        );
+        assert_eq!(
+            filetime_system_time(filetime(116_444_736_010_000_000)),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use windows::{
+    Win32::System::{
+        Console::{COORD, ClosePseudoConsole, CreatePseudoConsole, HPCON, ResizePseudoConsole},
+        Threading::{
+            DeleteProcThreadAttributeList, InitializeProcThreadAttributeList,
+            LPPROC_THREAD_ATTRIBUTE_LIST, PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE,
+            UpdateProcThreadAttribute,
+        },
+    },
+    core::Error,
+};
+
+use super::pipe::PipeHandle;
+
+fn coord(columns: u16, rows: u16) -> COORD {
+    COORD {
+        X: columns.min(i16::MAX as u16) as i16,
+        Y: rows.min(i16::MAX as u16) as i16,
+    }
+}
+
+/// A pseudo console (ConPTY) rendering a console program's screen as a
+/// stream of VT sequences, closed on drop.
+pub struct PseudoConsole(HPCON);
+
+// SAFETY: pseudo console handles may be resized and closed from any thread.
+unsafe impl Send for PseudoConsole {}
+unsafe impl Sync for PseudoConsole {}
+
+impl PseudoConsole {
+    /// Creates a pseudo console reading keyboard input from `input` and
+    /// writing its output to `output`. The console keeps its own copies of
+    /// both, so the caller may drop them afterwards.
+    pub fn new(
+        columns: u16,
+        rows: u16,
+        input: &PipeHandle,
+        output: &PipeHandle,
+    ) -> Result<Self, Error> {
+        Ok(Self(unsafe {
+            CreatePseudoConsole(coord(columns, rows), input.raw(), output.raw(), 0)
+        }?))
+    }
+
+    pub fn resize(&self, columns: u16, rows: u16) -> Result<(), Error> {
+        unsafe { ResizePseudoConsole(self.0, coord(columns, rows)) }
+    }
+
+    /// A process attribute list attaching a new process to this console.
+    pub fn attributes(&self) -> Result<AttributeList, Error> {
+        let mut size = 0;
+        // The first call only reports the size the list needs, and fails
+        // because no list was passed.
+        let _ = unsafe { InitializeProcThreadAttributeList(None, 1, None, &mut size) };
+        let mut buffer = vec![0usize; size.div_ceil(std::mem::size_of::<usize>())];
+        let list = LPPROC_THREAD_ATTRIBUTE_LIST(buffer.as_mut_ptr() as *mut _);
+        unsafe { InitializeProcThreadAttributeList(Some(list), 1, None, &mut size) }?;
+        let attributes = AttributeList {
+            list,
+            _buffer: buffer,
+        };
+        unsafe {
+            UpdateProcThreadAttribute(
+                list,
+                0,
+                PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE as usize,
+                Some(self.0.0 as *const _),
+                std::mem::size_of::<HPCON>(),
+                None,
+                None,
+            )
+        }?;
+        Ok(attributes)
+    }
+}
+
+impl Drop for PseudoConsole {
+    fn drop(&mut self) {
+        unsafe { ClosePseudoConsole(self.0) };
+    }
+}
+
+/// An initialized `PROC_THREAD_ATTRIBUTE_LIST`, for `STARTUPINFOEXW`.
+pub struct AttributeList {
+    list: LPPROC_THREAD_ATTRIBUTE_LIST,
+    _buffer: Vec<usize>,
+}
+
+impl AttributeList {
+    pub fn raw(&self) -> LPPROC_THREAD_ATTRIBUTE_LIST {
+        self.list
+    }
+}
+
+impl Drop for AttributeList {
+    fn drop(&mut self) {
+        unsafe { DeleteProcThreadAttributeList(self.list) };
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::launch::Launch;
+use super::pipe::{PipeHandle, PipeWriter};
+use super::pseudo_console::PseudoConsole;
+use windows::core::Error;
+
+/// A console program started by
+/// [`super::manager::ProcessManager::open_console`], with the input side of
+/// its console.
+pub struct ConsoleSession {
+    pub launch: Arc<Launch>,
+    input: PipeWriter,
+    console: Mutex<Option<PseudoConsole>>,
+    pseudo_console: bool,
+}
+
+impl ConsoleSession {
+    pub fn new(launch: Arc<Launch>, input: PipeHandle, console: Option<PseudoConsole>) -> Self {
+        Self {
+            launch,
+            input: PipeWriter::new(Some(input)),
+            pseudo_console: console.is_some(),
+            console: Mutex::new(console),
+        }
+    }
+
+    /// Whether the program runs in a pseudo console, rather than with plain
+    /// pipes as its standard handles.
+    pub fn is_pseudo_console(&self) -> bool {
+        self.pseudo_console
+    }
+
+    /// Types `data` into the console, failing with `ERROR_BROKEN_PIPE` once
+    /// input is closed.
+    pub fn write(&self, data: &[u8]) -> Result<(), Error> {
+        self.input.write_all(data)
+    }
+
+    /// Closes input, cancelling a write the program is not reading.
+    pub fn close_input(&self) {
+        self.input.close();
+    }
+
+    /// Resizes the pseudo console. Programs running on plain pipes have no
+    /// screen to resize, so for them this does nothing.
+    pub fn resize(&self, columns: u16, rows: u16) -> Result<(), Error> {
+        match &*self.console.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(console) => console.resize(columns, rows),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes the pseudo console, ending its output stream.
+    pub fn close_console(&self) {
+        // Closing may wait for the output to be drained, so it happens
+        // outside the lock.
+        let console = self
+            .console
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        drop(console);
+    }
+}
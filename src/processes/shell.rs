@@ -0,0 +1,82 @@
+use super::process::ProcessInfo;
+use super::to_wide_string;
+use windows::{
+    Win32::{
+        System::{
+            Com::{
+                COINIT_APARTMENTTHREADED, COINIT_DISABLE_OLE1DDE, CoInitializeEx, CoUninitialize,
+            },
+            Threading::{GetProcessId, PROCESS_INFORMATION},
+        },
+        UI::{
+            Shell::{
+                SEE_MASK_FLAG_NO_UI, SEE_MASK_NO_CONSOLE, SEE_MASK_NOASYNC,
+                SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW, ShellExecuteExW,
+            },
+            WindowsAndMessaging::SW_SHOWNORMAL,
+        },
+    },
+    core::{Error, PCWSTR},
+};
+
+fn optional_wide_string(s: Option<&str>) -> Option<Vec<u16>> {
+    s.filter(|s| !s.is_empty()).map(to_wide_string)
+}
+
+fn as_pcwstr(s: &Option<Vec<u16>>) -> PCWSTR {
+    s.as_ref()
+        .map(|s| PCWSTR(s.as_ptr()))
+        .unwrap_or_else(PCWSTR::null)
+}
+
+/// Opens `file` the way Explorer would: shortcuts are resolved, documents
+/// and URLs go to their associated program and `.msi` packages to msiexec.
+///
+/// Returns `None` when the shell handed `file` to a program that was already
+/// running (over DDE, say), so that no new process was started.
+pub fn execute(
+    file: &str,
+    verb: Option<&str>,
+    parameters: &str,
+    directory: Option<&str>,
+    new_console: bool,
+) -> Result<Option<ProcessInfo>, Error> {
+    let file = to_wide_string(file);
+    let verb = optional_wide_string(verb);
+    let parameters = optional_wide_string(Some(parameters));
+    let directory = optional_wide_string(directory);
+
+    let mut mask = SEE_MASK_NOCLOSEPROCESS | SEE_MASK_FLAG_NO_UI | SEE_MASK_NOASYNC;
+    if !new_console {
+        mask |= SEE_MASK_NO_CONSOLE;
+    }
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: mask,
+        lpVerb: as_pcwstr(&verb),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: as_pcwstr(&parameters),
+        lpDirectory: as_pcwstr(&directory),
+        nShow: SW_SHOWNORMAL.0,
+        ..Default::default()
+    };
+
+    // Some shell extensions and protocol handlers rely on COM, which the
+    // calling thread may not have initialized yet.
+    let initialized =
+        unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED | COINIT_DISABLE_OLE1DDE) }.is_ok();
+    let result = unsafe { ShellExecuteExW(&mut info) };
+    if initialized {
+        unsafe { CoUninitialize() };
+    }
+    result?;
+
+    if info.hProcess.is_invalid() {
+        return Ok(None);
+    }
+    Ok(Some(ProcessInfo(PROCESS_INFORMATION {
+        hProcess: info.hProcess,
+        dwProcessId: unsafe { GetProcessId(info.hProcess) },
+        ..Default::default()
+    })))
+}
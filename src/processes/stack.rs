@@ -0,0 +1,345 @@
+use std::{
+    ffi::{CStr, c_void},
+    sync::{Mutex, PoisonError},
+};
+
+use super::module::{Module, ModuleSnapshot};
+use super::process::ProcessHandle;
+use super::thread::{ThreadHandle, ThreadSnapshot};
+use windows::{
+    Win32::{
+        Foundation::{ERROR_INVALID_PARAMETER, HANDLE},
+        System::{
+            Diagnostics::Debug::{
+                AddrModeFlat, MAX_SYM_NAME, STACKFRAME64, SYMBOL_INFO, SYMOPT_DEFERRED_LOADS,
+                SYMOPT_FAIL_CRITICAL_ERRORS, SYMOPT_UNDNAME, StackWalk64, SymCleanup, SymFromAddr,
+                SymFunctionTableAccess64, SymGetModuleBase64, SymInitialize, SymSetOptions,
+            },
+            SystemInformation::IMAGE_FILE_MACHINE,
+            Threading::{
+                PROCESS_QUERY_INFORMATION, PROCESS_VM_READ, THREAD_GET_CONTEXT,
+                THREAD_QUERY_INFORMATION, THREAD_SUSPEND_RESUME,
+            },
+        },
+    },
+    core::{Error, HRESULT, PCSTR},
+};
+
+/// DbgHelp is single-threaded, so every use of it, here or by the crash
+/// dumps, goes through this lock.
+pub(crate) static DBGHELP: Mutex<()> = Mutex::new(());
+
+/// One return address on a thread's stack.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub address: u64,
+    /// The module the address lies in and the offset into it, when the
+    /// address lies in any module.
+    pub module: Option<(String, u64)>,
+    /// The function the address lies in and the offset into it, when
+    /// symbols for its module are available.
+    pub symbol: Option<(String, u64)>,
+}
+
+/// The stack of one thread, or why it could not be walked.
+pub struct ThreadStack {
+    pub tid: u32,
+    pub frames: Result<Vec<Frame>, Error>,
+}
+
+/// DbgHelp's symbol handler for one process, cleaned up on drop.
+struct Symbols(HANDLE);
+
+impl Symbols {
+    fn new(process: HANDLE) -> Result<Self, Error> {
+        unsafe {
+            SymSetOptions(SYMOPT_UNDNAME | SYMOPT_DEFERRED_LOADS | SYMOPT_FAIL_CRITICAL_ERRORS);
+            // Invading the process loads the modules it already has, which
+            // is what the stack walk needs to find their unwind tables.
+            SymInitialize(process, PCSTR::null(), true)?;
+        }
+        Ok(Self(process))
+    }
+
+    /// The function `address` lies in, and the offset into it.
+    fn symbol(&self, address: u64) -> Option<(String, u64)> {
+        // SYMBOL_INFO is followed by room for the rest of the name, in a
+        // u64 buffer to keep the struct aligned.
+        let mut buffer = vec![
+            0u64;
+            (std::mem::size_of::<SYMBOL_INFO>() + MAX_SYM_NAME as usize)
+                .div_ceil(std::mem::size_of::<u64>())
+        ];
+        let symbol = buffer.as_mut_ptr() as *mut SYMBOL_INFO;
+        unsafe {
+            (*symbol).SizeOfStruct = std::mem::size_of::<SYMBOL_INFO>() as u32;
+            (*symbol).MaxNameLen = MAX_SYM_NAME;
+        }
+
+        let mut displacement = 0;
+        unsafe { SymFromAddr(self.0, address, Some(&mut displacement), symbol) }.ok()?;
+        let name = unsafe { CStr::from_ptr((*symbol).Name.as_ptr()) };
+        Some((name.to_string_lossy().into_owned(), displacement))
+    }
+}
+
+impl Drop for Symbols {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = SymCleanup(self.0);
+        }
+    }
+}
+
+/// Walks the stack of every thread of process `pid`, suspending each thread
+/// only while its own stack is walked, and keeps at most `max_frames` frames
+/// per thread.
+///
+/// Frames are named after their function when symbols are available, and
+/// always after their module and the offset into it, so a trace stays
+/// useful for stripped binaries.
+pub fn capture(pid: u32, max_frames: usize) -> Result<Vec<ThreadStack>, Error> {
+    // Suspending the thread doing the walk would never end.
+    if pid == std::process::id() {
+        return Err(Error::from_hresult(HRESULT::from_win32(
+            ERROR_INVALID_PARAMETER.0,
+        )));
+    }
+
+    let process = ProcessHandle::open(pid, PROCESS_QUERY_INFORMATION | PROCESS_VM_READ)?;
+    let wow64 = process.is_wow64()?;
+    let modules: Vec<Module> = ModuleSnapshot::new(pid)?.collect();
+
+    let _dbghelp = DBGHELP.lock().unwrap_or_else(PoisonError::into_inner);
+    let symbols = Symbols::new(process.raw())?;
+    Ok(ThreadSnapshot::new()?
+        .filter(|thread| thread.owner_pid() == pid)
+        .map(|thread| ThreadStack {
+            tid: thread.tid(),
+            frames: walk_thread(&symbols, thread.tid(), wow64, max_frames).map(|addresses| {
+                addresses
+                    .into_iter()
+                    .map(|address| Frame {
+                        address,
+                        module: module_offset(&modules, address),
+                        symbol: symbols.symbol(address),
+                    })
+                    .collect()
+            }),
+        })
+        .collect())
+}
+
+fn module_offset(modules: &[Module], address: u64) -> Option<(String, u64)> {
+    modules
+        .iter()
+        .find(|module| {
+            (module.base_address()..module.base_address() + u64::from(module.size()))
+                .contains(&address)
+        })
+        .map(|module| (module.name(), address - module.base_address()))
+}
+
+/// Return addresses on the stack of thread `tid`, innermost first.
+fn walk_thread(
+    symbols: &Symbols,
+    tid: u32,
+    wow64: bool,
+    max_frames: usize,
+) -> Result<Vec<u64>, Error> {
+    let thread = ThreadHandle::open(
+        tid,
+        THREAD_SUSPEND_RESUME | THREAD_GET_CONTEXT | THREAD_QUERY_INFORMATION,
+    )?;
+    thread.suspend()?;
+    let walked = walk(symbols, &thread, wow64, max_frames);
+    if let Err(error) = thread.resume() {
+        tracing::warn!("Failed to resume thread {tid} after walking its stack: {error}");
+    }
+    walked
+}
+
+#[cfg(target_arch = "x86_64")]
+fn walk(
+    symbols: &Symbols,
+    thread: &ThreadHandle,
+    wow64: bool,
+    max_frames: usize,
+) -> Result<Vec<u64>, Error> {
+    use windows::Win32::System::SystemInformation::{
+        IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_I386,
+    };
+
+    // A WOW64 thread stopped in 32-bit code is walked with its 32-bit
+    // registers; its 64-bit ones only show the WOW64 layer.
+    if wow64 {
+        let mut context = thread.wow64_context()?;
+        let frame = start_frame(context.Eip.into(), context.Ebp.into(), context.Esp.into());
+        return Ok(walk_frames(
+            symbols,
+            thread,
+            IMAGE_FILE_MACHINE_I386,
+            frame,
+            &mut context as *mut _ as *mut c_void,
+            max_frames,
+        ));
+    }
+    let mut context = thread.context()?;
+    let frame = start_frame(context.0.Rip, context.0.Rbp, context.0.Rsp);
+    Ok(walk_frames(
+        symbols,
+        thread,
+        IMAGE_FILE_MACHINE_AMD64,
+        frame,
+        &mut context.0 as *mut _ as *mut c_void,
+        max_frames,
+    ))
+}
+
+#[cfg(target_arch = "x86")]
+fn walk(
+    symbols: &Symbols,
+    thread: &ThreadHandle,
+    _wow64: bool,
+    max_frames: usize,
+) -> Result<Vec<u64>, Error> {
+    use windows::Win32::System::SystemInformation::IMAGE_FILE_MACHINE_I386;
+
+    // A 32-bit bridge only ever sees the 32-bit registers, WOW64 or not.
+    let mut context = thread.context()?;
+    let frame = start_frame(
+        context.0.Eip.into(),
+        context.0.Ebp.into(),
+        context.0.Esp.into(),
+    );
+    Ok(walk_frames(
+        symbols,
+        thread,
+        IMAGE_FILE_MACHINE_I386,
+        frame,
+        &mut context.0 as *mut _ as *mut c_void,
+        max_frames,
+    ))
+}
+
+#[cfg(target_arch = "aarch64")]
+fn walk(
+    symbols: &Symbols,
+    thread: &ThreadHandle,
+    _wow64: bool,
+    max_frames: usize,
+) -> Result<Vec<u64>, Error> {
+    use windows::Win32::System::SystemInformation::IMAGE_FILE_MACHINE_ARM64;
+
+    // Emulated x86 threads have no 32-bit context to walk here, so they
+    // are walked with their native registers, like any other.
+    let mut context = thread.context()?;
+    let frame = start_frame(
+        context.0.Pc,
+        unsafe { context.0.Anonymous.Anonymous.Fp },
+        context.0.Sp,
+    );
+    Ok(walk_frames(
+        symbols,
+        thread,
+        IMAGE_FILE_MACHINE_ARM64,
+        frame,
+        &mut context.0 as *mut _ as *mut c_void,
+        max_frames,
+    ))
+}
+
+fn start_frame(pc: u64, frame: u64, stack: u64) -> STACKFRAME64 {
+    let mut start = STACKFRAME64::default();
+    start.AddrPC.Offset = pc;
+    start.AddrPC.Mode = AddrModeFlat;
+    start.AddrFrame.Offset = frame;
+    start.AddrFrame.Mode = AddrModeFlat;
+    start.AddrStack.Offset = stack;
+    start.AddrStack.Mode = AddrModeFlat;
+    start
+}
+
+/// Steps `frame` outwards until the walk ends, fails or stops making
+/// progress. `context` must match `machine`, and StackWalk64 updates it as
+/// it unwinds.
+fn walk_frames(
+    symbols: &Symbols,
+    thread: &ThreadHandle,
+    machine: IMAGE_FILE_MACHINE,
+    mut frame: STACKFRAME64,
+    context: *mut c_void,
+    max_frames: usize,
+) -> Vec<u64> {
+    let mut addresses = Vec::new();
+    let mut previous = None;
+    while addresses.len() < max_frames {
+        let stepped = unsafe {
+            StackWalk64(
+                machine.0.into(),
+                symbols.0,
+                thread.raw(),
+                &mut frame,
+                context,
+                None,
+                Some(function_table_access),
+                Some(module_base),
+                None,
+            )
+        };
+        let address = frame.AddrPC.Offset;
+        // A corrupt stack can make the walk repeat the same frame forever.
+        let current = Some((address, frame.AddrStack.Offset));
+        if !stepped.as_bool() || address == 0 || current == previous {
+            break;
+        }
+        previous = current;
+        addresses.push(address);
+    }
+    addresses
+}
+
+unsafe extern "system" fn function_table_access(process: HANDLE, address: u64) -> *mut c_void {
+    unsafe { SymFunctionTableAccess64(process, address) }
+}
+
+unsafe extern "system" fn module_base(process: HANDLE, address: u64) -> u64 {
+    unsafe { SymGetModuleBase64(process, address) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processes::testing;
+
+    #[test]
+    fn walks_the_stacks_of_another_process() {
+        let mut child = testing::paused_child();
+        // Give the loader time to map the modules the walk unwinds through.
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let stacks = capture(child.id(), 64);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let stacks = stacks.unwrap();
+        assert!(!stacks.is_empty());
+        assert!(stacks.iter().any(|stack| {
+            stack.frames.as_ref().is_ok_and(|frames| {
+                !frames.is_empty()
+                    && frames.iter().all(|frame| frame.address != 0)
+                    && frames.iter().any(|frame| frame.module.is_some())
+            })
+        }));
+    }
+
+    #[test]
+    fn refuses_to_walk_its_own_process() {
+        assert_eq!(
+            capture(std::process::id(), 64)
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_INVALID_PARAMETER.0))
+        );
+    }
+}
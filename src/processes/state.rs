@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+/// Directory inside the prefix where the bridge keeps what it must remember
+/// across restarts, as files of one record per line with tab-separated,
+/// [`escape`]d fields.
+pub fn directory() -> PathBuf {
+    std::env::var_os("ProgramData")
+        .map_or_else(|| r"C:\ProgramData".into(), PathBuf::from)
+        .join("WineBridge")
+}
+
+/// Escapes the characters that would end a line or field early.
+pub fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '\t' => escaped.push_str(r"\t"),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape`], returning `None` for an unknown escape sequence.
+pub fn unescape(escaped: &str) -> Option<String> {
+    let mut field = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            field.push(c);
+            continue;
+        }
+        field.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(field)
+}
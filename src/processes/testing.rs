@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use super::manager::ProcessManager;
+use next_proto::winebridge;
+
+/// A name no other test run uses at the same time, for launch ids, job
+/// objects and state directories.
+pub fn unique_name(name: &str) -> String {
+    format!("bottles-winebridge-{name}-{}", std::process::id())
+}
+
+/// Full path of program `name` in the system directory, as launches take
+/// their executable literally rather than searching `PATH` for it.
+pub fn system_program(name: &str) -> String {
+    let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\windows".to_string());
+    format!(r"{root}\system32\{name}.exe")
+}
+
+/// Starts `cmd /c pause`, which waits for a key press that never comes,
+/// until it is killed or its job is terminated.
+pub fn paused_child() -> std::process::Child {
+    std::process::Command::new("cmd")
+        .args(["/c", "pause"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+/// A launch of `cmd /c pause` as `id`, which waits until its redirected
+/// stdin is closed. It dies with the bridge, so it stays out of the launch
+/// table.
+pub fn pause_launch(id: &str) -> winebridge::LaunchProcessRequest {
+    winebridge::LaunchProcessRequest {
+        id: id.to_string(),
+        executable: system_program("cmd"),
+        arguments: vec!["/c".to_string(), "pause".to_string()],
+        redirect_stdin: true,
+        lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+        ..Default::default()
+    }
+}
+
+/// A launch of notepad as `id`, which runs until it is closed and uses no
+/// CPU while it waits. It dies with the bridge.
+pub fn notepad_launch(id: &str) -> winebridge::LaunchProcessRequest {
+    winebridge::LaunchProcessRequest {
+        id: id.to_string(),
+        executable: system_program("notepad"),
+        lifecycle: winebridge::LaunchLifecycle::KillOnBridgeExit as i32,
+        ..Default::default()
+    }
+}
+
+/// A fresh temporary directory for a launch table and history, rather than
+/// the prefix.
+pub fn state_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(unique_name(name));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+/// A manager whose launch table and history live in a fresh
+/// [`state_directory`].
+pub fn manager(name: &str) -> ProcessManager {
+    ProcessManager::in_directory(&state_directory(name))
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, SystemTime};
+
+use super::process::{filetime_duration, filetime_system_time};
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, FILETIME, HANDLE},
+        System::{
+            Diagnostics::{
+                Debug::{
+                    CONTEXT, CONTEXT_FLAGS, GetThreadContext, WOW64_CONTEXT, WOW64_CONTEXT_FULL,
+                    Wow64GetThreadContext,
+                },
+                ToolHelp::{
+                    CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First,
+                    Thread32Next,
+                },
+            },
+            Threading::{
+                GetThreadTimes, OpenThread, ResumeThread, SuspendThread, THREAD_ACCESS_RIGHTS,
+            },
+        },
+    },
+    core::Error,
+};
+
+#[cfg(target_arch = "x86_64")]
+pub const CONTEXT_FULL: CONTEXT_FLAGS =
+    windows::Win32::System::Diagnostics::Debug::CONTEXT_FULL_AMD64;
+#[cfg(target_arch = "x86")]
+pub const CONTEXT_FULL: CONTEXT_FLAGS =
+    windows::Win32::System::Diagnostics::Debug::CONTEXT_FULL_X86;
+#[cfg(target_arch = "aarch64")]
+pub const CONTEXT_FULL: CONTEXT_FLAGS =
+    windows::Win32::System::Diagnostics::Debug::CONTEXT_FULL_ARM64;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")))]
+compile_error!("thread contexts are only supported on x86, x86_64 and aarch64");
+
+/// `GetThreadContext` needs a 16-byte aligned `CONTEXT` on x86_64 and
+/// aarch64, which the generated struct does not guarantee by itself.
+#[repr(C, align(16))]
+pub struct AlignedContext(pub CONTEXT);
+
+/// When a thread started and how much CPU time it has used so far.
+#[derive(Debug, Clone)]
+pub struct ThreadTimes {
+    pub created: SystemTime,
+    pub kernel_time: Duration,
+    pub user_time: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Thread(THREADENTRY32);
+
+impl Thread {
+    pub fn tid(&self) -> u32 {
+        self.0.th32ThreadID
+    }
+
+    pub fn owner_pid(&self) -> u32 {
+        self.0.th32OwnerProcessID
+    }
+
+    /// Priority the thread was created with, before any dynamic boost.
+    pub fn base_priority(&self) -> i32 {
+        self.0.tpBasePri
+    }
+}
+
+/// Every thread of every process on the system, as of when the snapshot was
+/// taken.
+pub struct ThreadSnapshot {
+    handle: HANDLE,
+    initialized: bool,
+}
+
+impl ThreadSnapshot {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            handle: unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) }?,
+            initialized: false,
+        })
+    }
+}
+
+impl Iterator for ThreadSnapshot {
+    type Item = Thread;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+
+        if !self.initialized {
+            unsafe { Thread32First(self.handle, &mut entry) }.ok()?;
+            self.initialized = true;
+        } else {
+            unsafe { Thread32Next(self.handle, &mut entry) }.ok()?;
+        }
+
+        Some(Thread(entry))
+    }
+}
+
+impl Drop for ThreadSnapshot {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// A handle to a thread opened by tid, closed on drop.
+pub struct ThreadHandle(HANDLE);
+
+// SAFETY: the handle is owned exclusively by this value and thread handles
+// may be used and closed from any thread.
+unsafe impl Send for ThreadHandle {}
+unsafe impl Sync for ThreadHandle {}
+
+impl ThreadHandle {
+    pub fn open(tid: u32, access: THREAD_ACCESS_RIGHTS) -> Result<Self, Error> {
+        Ok(Self(unsafe { OpenThread(access, false, tid) }?))
+    }
+
+    pub fn raw(&self) -> HANDLE {
+        self.0
+    }
+
+    /// Increments the thread's suspend count, stopping it if it was running.
+    pub fn suspend(&self) -> Result<(), Error> {
+        if unsafe { SuspendThread(self.0) } == u32::MAX {
+            return Err(Error::from_thread());
+        }
+        Ok(())
+    }
+
+    pub fn times(&self) -> Result<ThreadTimes, Error> {
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        unsafe { GetThreadTimes(self.0, &mut creation, &mut exit, &mut kernel, &mut user) }?;
+
+        Ok(ThreadTimes {
+            created: filetime_system_time(creation),
+            kernel_time: filetime_duration(kernel),
+            user_time: filetime_duration(user),
+        })
+    }
+
+    /// The thread's integer and control registers, which only hold still
+    /// while the thread is suspended. The handle needs `THREAD_GET_CONTEXT`.
+    pub fn context(&self) -> Result<Box<AlignedContext>, Error> {
+        let mut context = Box::new(AlignedContext(CONTEXT {
+            ContextFlags: CONTEXT_FULL,
+            ..Default::default()
+        }));
+        unsafe { GetThreadContext(self.0, &mut context.0) }?;
+        Ok(context)
+    }
+
+    /// Like [`Self::context`], but the registers of the 32-bit half of a
+    /// thread in a WOW64 process.
+    pub fn wow64_context(&self) -> Result<WOW64_CONTEXT, Error> {
+        let mut context = WOW64_CONTEXT {
+            ContextFlags: WOW64_CONTEXT_FULL,
+            ..Default::default()
+        };
+        unsafe { Wow64GetThreadContext(self.0, &mut context) }?;
+        Ok(context)
+    }
+
+    /// Decrements the thread's suspend count, letting it run again once the
+    /// count reaches zero.
+    pub fn resume(&self) -> Result<(), Error> {
+        if unsafe { ResumeThread(self.0) } == u32::MAX {
+            return Err(Error::from_thread());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ThreadHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::launch::Launch;
+use windows::Win32::Foundation::ERROR_TIMEOUT;
+
+/// How often the watchdog checks a launch's deadline and CPU usage.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Exit code the processes of a launch are terminated with when the watchdog
+/// fires.
+pub const WATCHDOG_EXIT_CODE: u32 = ERROR_TIMEOUT.0;
+
+/// Why the watchdog terminated a launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watchdog {
+    /// The launch ran for longer than its timeout.
+    Timeout,
+    /// The launch used no CPU time for longer than its idle timeout.
+    Idle,
+}
+
+/// Terminates the job of `launch` once it has run for `timeout`, or used no
+/// CPU for `idle_timeout`, on a dedicated thread that outlives the client
+/// which asked for the launch. The thread ends with the job's last process.
+///
+/// A suspended launch uses no CPU by design, so the idle clock stands still
+/// while it is suspended and starts over once it is resumed.
+pub fn watch(launch: Arc<Launch>, timeout: Option<Duration>, idle_timeout: Option<Duration>) {
+    std::thread::spawn(move || {
+        let deadline = timeout.map(|timeout| launch.started + timeout);
+        let mut cpu_time = Duration::ZERO;
+        let mut busy = Instant::now();
+
+        loop {
+            std::thread::sleep(WATCHDOG_INTERVAL);
+            match launch.job.active_processes() {
+                Ok(0) => return,
+                Ok(_) => {}
+                Err(error) => {
+                    tracing::warn!("Watchdog failed to query a launch's job: {error}");
+                    return;
+                }
+            }
+
+            let now = Instant::now();
+            if launch.is_suspended() {
+                busy = now;
+            } else if let Ok(used) = launch.job.cpu_time()
+                && used > cpu_time
+            {
+                cpu_time = used;
+                busy = now;
+            }
+
+            let fired = if deadline.is_some_and(|deadline| now >= deadline) {
+                Watchdog::Timeout
+            } else if idle_timeout.is_some_and(|idle| now - busy >= idle) {
+                Watchdog::Idle
+            } else {
+                continue;
+            };
+
+            tracing::info!(
+                "Terminating launch of process {} ({fired:?} watchdog)",
+                launch.process.pid()
+            );
+            launch.record_watchdog(fired);
+            if let Err(error) = launch.job.terminate(WATCHDOG_EXIT_CODE) {
+                tracing::warn!("Watchdog failed to terminate a launch's job: {error}");
+            }
+            return;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processes::testing;
+    use next_proto::winebridge;
+    use windows::core::HRESULT;
+
+    const EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn launch(name: &str, timeout_ms: Option<u32>, idle_timeout_ms: Option<u32>) -> Arc<Launch> {
+        let processes = testing::manager(name);
+        let request = winebridge::LaunchProcessRequest {
+            timeout_ms,
+            idle_timeout_ms,
+            ..testing::notepad_launch(&testing::unique_name(name))
+        };
+        let reservation = processes.reserve(&request).unwrap();
+        processes.execute(reservation, request).unwrap().unwrap()
+    }
+
+    #[test]
+    fn terminates_launches_past_their_timeout() {
+        let launch = launch("watchdog-timeout", Some(1000), None);
+
+        let exit = launch.wait(Some(EXIT_TIMEOUT)).unwrap();
+        assert_eq!(exit.watchdog, Some(Watchdog::Timeout));
+        assert_eq!(exit.exit_code, WATCHDOG_EXIT_CODE);
+    }
+
+    #[test]
+    fn terminates_launches_idle_past_their_idle_timeout() {
+        let launch = launch("watchdog-idle", None, Some(1000));
+
+        let exit = launch.wait(Some(EXIT_TIMEOUT)).unwrap();
+        assert_eq!(exit.watchdog, Some(Watchdog::Idle));
+        assert_eq!(exit.exit_code, WATCHDOG_EXIT_CODE);
+    }
+
+    #[test]
+    fn leaves_suspended_launches_running() {
+        let launch = launch("watchdog-suspended", None, Some(1000));
+        launch.suspend().unwrap();
+
+        std::thread::sleep(Duration::from_secs(3));
+        assert_eq!(
+            launch
+                .wait(Some(Duration::ZERO))
+                .err()
+                .map(|error| error.code()),
+            Some(HRESULT::from_win32(ERROR_TIMEOUT.0))
+        );
+
+        // The idle clock starts over once the launch runs again.
+        launch.resume().unwrap();
+        let exit = launch.wait(Some(EXIT_TIMEOUT)).unwrap();
+        assert_eq!(exit.watchdog, Some(Watchdog::Idle));
+        assert_eq!(exit.exit_code, WATCHDOG_EXIT_CODE);
+    }
+}
@@ -50,6 +50,7 @@ pub fn get_value(hive: i32, subkey: &str, name: &str) -> Result<winebridge::Regi
 
 pub fn set_value(hive: i32, subkey: &str, name: &str, value: ProtoValue) -> Result<(), Status> {
     validate_name(name)?;
+    validate_value(&value)?;
     let root = resolve_root(hive, subkey)?;
     let key = root
         .options()
@@ -62,24 +63,9 @@ pub fn set_value(hive: i32, subkey: &str, name: &str, value: ProtoValue) -> Resu
         ProtoValue::Binary(value) => key.set_bytes(name, Type::Bytes, &value),
         ProtoValue::Dword(value) => key.set_u32(name, value),
         ProtoValue::Qword(value) => key.set_u64(name, value),
-        ProtoValue::String(value) => {
-            validate_string(&value)?;
-            key.set_string(name, value)
-        }
-        ProtoValue::ExpandString(value) => {
-            validate_string(&value)?;
-            key.set_expand_string(name, value)
-        }
+        ProtoValue::String(value) => key.set_string(name, value),
+        ProtoValue::ExpandString(value) => key.set_expand_string(name, value),
         ProtoValue::MultiString(value) => {
-            if value
-                .values
-                .iter()
-                .any(|value| value.is_empty() || value.contains('\0'))
-            {
-                return Err(Status::invalid_argument(
-                    "registry multi-string values must be non-empty and contain no NUL bytes",
-                ));
-            }
             let values: Vec<_> = value.values.iter().map(String::as_str).collect();
             key.set_multi_string(name, &values)
         }
@@ -98,6 +84,31 @@ pub fn delete_value(hive: i32, subkey: &str, name: &str) -> Result<(), Status> {
         .map_err(status::windows)
 }
 
+/// Checks the address of a registry value without touching the registry,
+/// for requests that only write it later.
+pub fn validate_address(hive: i32, subkey: &str, name: &str) -> Result<(), Status> {
+    validate_name(name)?;
+    resolve_root(hive, subkey).map(drop)
+}
+
+/// Checks that `value` can be stored as given.
+pub fn validate_value(value: &ProtoValue) -> Result<(), Status> {
+    match value {
+        ProtoValue::String(value) | ProtoValue::ExpandString(value) => validate_string(value),
+        ProtoValue::MultiString(value)
+            if value
+                .values
+                .iter()
+                .any(|value| value.is_empty() || value.contains('\0')) =>
+        {
+            Err(Status::invalid_argument(
+                "registry multi-string values must be non-empty and contain no NUL bytes",
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
 fn resolve_root(hive: i32, subkey: &str) -> Result<&'static Key, Status> {
     if subkey.is_empty() || subkey.contains('\0') {
         return Err(Status::invalid_argument(
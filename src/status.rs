@@ -1,8 +1,9 @@
 use tonic::Status;
 use windows::Win32::Foundation::{
-    ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_FILE_NOT_FOUND, ERROR_INVALID_DATA,
-    ERROR_INVALID_PARAMETER, ERROR_PATH_NOT_FOUND, ERROR_SERVICE_ALREADY_RUNNING,
-    ERROR_SERVICE_DOES_NOT_EXIST, ERROR_SERVICE_EXISTS, ERROR_SERVICE_NOT_ACTIVE,
+    ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_BROKEN_PIPE, ERROR_FILE_NOT_FOUND,
+    ERROR_INVALID_DATA, ERROR_INVALID_PARAMETER, ERROR_INVALID_WINDOW_HANDLE, ERROR_NO_DATA,
+    ERROR_NOT_FOUND, ERROR_PATH_NOT_FOUND, ERROR_SERVICE_ALREADY_RUNNING,
+    ERROR_SERVICE_DOES_NOT_EXIST, ERROR_SERVICE_EXISTS, ERROR_SERVICE_NOT_ACTIVE, ERROR_TIMEOUT,
 };
 use windows::core::{Error, HRESULT};
 
@@ -10,7 +11,9 @@ pub fn windows(error: Error) -> Status {
     let code = error.code();
     if code == HRESULT::from_win32(ERROR_FILE_NOT_FOUND.0)
         || code == HRESULT::from_win32(ERROR_PATH_NOT_FOUND.0)
+        || code == HRESULT::from_win32(ERROR_NOT_FOUND.0)
         || code == HRESULT::from_win32(ERROR_SERVICE_DOES_NOT_EXIST.0)
+        || code == HRESULT::from_win32(ERROR_INVALID_WINDOW_HANDLE.0)
     {
         Status::not_found(error.to_string())
     } else if code == HRESULT::from_win32(ERROR_ALREADY_EXISTS.0)
@@ -23,10 +26,14 @@ pub fn windows(error: Error) -> Status {
         Status::invalid_argument(error.to_string())
     } else if code == HRESULT::from_win32(ERROR_SERVICE_ALREADY_RUNNING.0)
         || code == HRESULT::from_win32(ERROR_SERVICE_NOT_ACTIVE.0)
+        || code == HRESULT::from_win32(ERROR_BROKEN_PIPE.0)
+        || code == HRESULT::from_win32(ERROR_NO_DATA.0)
     {
         Status::failed_precondition(error.to_string())
     } else if code == HRESULT::from_win32(ERROR_INVALID_DATA.0) {
         Status::data_loss(error.to_string())
+    } else if code == HRESULT::from_win32(ERROR_TIMEOUT.0) {
+        Status::deadline_exceeded(error.to_string())
     } else {
         Status::internal(error.to_string())
     }
@@ -68,6 +75,10 @@ mod tests {
             windows(error(ERROR_ALREADY_EXISTS.0)).code(),
             Code::AlreadyExists
         );
+        assert_eq!(
+            windows(error(ERROR_TIMEOUT.0)).code(),
+            Code::DeadlineExceeded
+        );
     }
 
     #[test]
@@ -0,0 +1,30 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use tonic::Status;
+
+/// Adapts the receiving half of a channel fed by a worker thread into the
+/// response stream of a server-streaming RPC.
+///
+/// Dropping the stream (the client disconnecting) closes the channel, which
+/// the worker observes as a failed `blocking_send` and treats as its signal to
+/// stop.
+pub struct ReceiverStream<T>(mpsc::Receiver<T>);
+
+impl<T> ReceiverStream<T> {
+    pub fn new(receiver: mpsc::Receiver<T>) -> Self {
+        Self(receiver)
+    }
+}
+
+impl<T> Stream for ReceiverStream<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx).map(|item| item.map(Ok))
+    }
+}